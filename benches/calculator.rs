@@ -0,0 +1,43 @@
+/*
+Benchmarks of the compiled evaluator Calculator uses against the stack interpreter it replaced (see interpreter.rs), each calculating an
+expression at the points a graph of it samples across the viewport. Both are given the same optimized AST, so that only the evaluators are
+compared. Run with cargo bench --features bench.
+*/
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use graphing_calculator::bench::{generate_calculator, generate_stack_calculator, optimize, parse, scan};
+
+//expressions benchmarked, with the names their results are reported under
+const EXPRESSIONS: &[(&str, &str)] = &[
+    ("polynomial", "3x^4 - 2x^3 + x^2 - 5x + 7"),
+    ("shared", "sin(x)^2 + sin(x) cos(x)"),
+    ("parameters", "a sin(b x + c) + cos(2x) / 2"),
+    ("functions", "max(x, sin(x), cos(x)) + sqrt(abs(x)) + ln(x^2 + 1)"),
+    ("piecewise", "{x < -1: -x^2, x < 1: x^3, sqrt(x) + sqrt(x) x}")
+];
+
+//number of points sampled across the viewport, as when graphing with the default step size
+const SAMPLES: usize = 5120;
+
+//function to calculate f at each sampled x from -5 to 5
+fn sample(mut f: impl FnMut(f64) -> Option<f64>) {
+    for i in 0..SAMPLES {
+        let x = -5.0 + 10.0 * i as f64 / SAMPLES as f64;
+        black_box(f(black_box(x)));
+    }
+}
+
+fn calculate(c: &mut Criterion) {
+    for (name, expression) in EXPRESSIONS {
+        let ast = optimize(parse(&scan(expression).unwrap()).unwrap());
+        let mut interpreted = generate_stack_calculator(ast.clone()).unwrap();
+        let mut compiled = generate_calculator(ast).unwrap();
+        let mut group = c.benchmark_group(*name);
+        group.bench_function("interpreted", |b| b.iter(|| sample(|x| interpreted.calculate(x))));
+        group.bench_function("compiled", |b| b.iter(|| sample(|x| compiled.calculate(x))));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, calculate);
+criterion_main!(benches);
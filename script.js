@@ -1,4 +1,4 @@
-import init, { run, initialize, reset, expand_cache, toggle_mode } from "./pkg/graphing_calculator.js";
+import init, { run, initialize, initialize_parametric, reset, expand_cache, set_mode, CoordinateMode, set_parameter, get_parameter, set_theta_range, clear_theta_range, get_theta_range, set_marker, MarkerShape, set_derivatives } from "./pkg/graphing_calculator.js";
 init().then(() => {
     //resize canvas if necessary
     const canvas = document.getElementById("canvas");
@@ -197,36 +197,103 @@ init().then(() => {
         }
     })
 
-    //event handlers for function inputs
-    let inputs = document.getElementsByClassName("function-input");
-    for (let i = 0; i < inputs.length; ++i) {
-        let el = inputs[i];
-        el.addEventListener("keyup", () => {
-            keepDrawing = false;
-            let functions = [];
-            for (let el of inputs) {
-                //disregard empty inputs
-                if (el.value !== "") {
-                    functions.push(el.value);
+    //function to show one slider for each distinct parameter used by the current expressions
+    let parametersContainer = document.getElementById("parameters-container");
+    let updateSliders = (result) => {
+        let names = [];
+        for (let diagnostic of result) {
+            for (let name of diagnostic.parameters) {
+                if (!names.includes(name)) {
+                    names.push(name);
                 }
             }
-            //completely reset all global app state, since we have new input, then reinitialize it based on new input
-            reset();
-            let result = initialize(functions);
-            //draw new function graphs
-            run(currentView[0], currentView[1], currentView[2], currentView[3]);
-            if(!expandTimeout) {
-                expand();
-            }
-            //indicate no error for this input
-            el.className = "function-input";
-            for (let r of result) {
-                if (!r && el.value !== "") {
-                    //indicate error for input if its expression is invalid
-                    el.className = "function-input error";
+        }
+        names.sort();
+        parametersContainer.replaceChildren();
+        for (let name of names) {
+            let div = document.createElement("div");
+            let label = document.createElement("label");
+            let slider = document.createElement("input");
+            let value = get_parameter(name);
+            slider.type = "range";
+            slider.id = "parameter-" + name;
+            slider.min = -10;
+            slider.max = 10;
+            slider.step = 0.1;
+            slider.value = value;
+            label.htmlFor = slider.id;
+            label.textContent = name + " = " + value;
+            slider.addEventListener("input", () => {
+                let value = parseFloat(slider.value);
+                label.textContent = name + " = " + value;
+                keepDrawing = false;
+                //only the graphs using this parameter are recalculated
+                set_parameter(name, value);
+                if(!expandTimeout) {
+                    expand();
                 }
-            }
-        })
+            });
+            div.appendChild(label);
+            div.appendChild(slider);
+            parametersContainer.appendChild(div);
+        }
+    }
+
+    //function to indicate an error on an input whose expression is invalid, or show how a valid expression was interpreted
+    let showDiagnostic = (input, diagnostic, className) => {
+        if (diagnostic.success) {
+            input.className = className;
+            input.title = diagnostic.expression;
+        }
+        else {
+            input.className = className + " error";
+            input.title = diagnostic.message;
+        }
+    }
+
+    //function to pass every function input and the parametric curve to the calculator - returns the diagnostics for both
+    let inputs = document.getElementsByClassName("function-input");
+    let parametricX = document.getElementById("parametric-x");
+    let parametricY = document.getElementById("parametric-y");
+    let tStart = document.getElementById("parametric-t-start");
+    let tEnd = document.getElementById("parametric-t-end");
+    let initializeInputs = () => {
+        //pass every input, including empty ones, so that each diagnostic lines up with its input
+        let functions = [];
+        for (let el of inputs) {
+            functions.push(el.value);
+        }
+        let result = initialize(functions);
+        let curve = { x: parametricX.value, y: parametricY.value, t_start: parseFloat(tStart.value) || 0, t_end: parseFloat(tEnd.value) || 0 };
+        let parametricResult = initialize_parametric([curve]);
+        return [result, parametricResult];
+    }
+
+    //event handlers for function inputs and the parametric curve
+    let inputChanged = () => {
+        keepDrawing = false;
+        //completely reset all global app state, since we have new input, then reinitialize it based on new input
+        reset();
+        let [result, parametricResult] = initializeInputs();
+        //draw new function graphs
+        run(currentView[0], currentView[1], currentView[2], currentView[3]);
+        if(!expandTimeout) {
+            expand();
+        }
+        for (let j = 0; j < inputs.length; ++j) {
+            showDiagnostic(inputs[j], result[j], "function-input");
+        }
+        showDiagnostic(parametricX, parametricResult[0].x, "parametric-input");
+        showDiagnostic(parametricY, parametricResult[0].y, "parametric-input");
+        updateDerivativeText(result);
+        updateSliders(result.concat(parametricResult[0].x, parametricResult[0].y));
+        updateThetaPlaceholders();
+    }
+    for (let el of [...inputs, parametricX, parametricY, tStart, tEnd]) {
+        el.addEventListener("keyup", inputChanged);
+    }
+    for (let el of [tStart, tEnd]) {
+        el.addEventListener("change", inputChanged);
     }
 
     //resize canvas on window resize
@@ -238,14 +305,7 @@ init().then(() => {
             canvas.height = Math.min(height, 700);
             canvas.width = Math.min(height, 700);
             reset();
-            let functions = [];
-            for (let el of inputs) {
-                //disregard empty inputs
-                if (el.value !== "") {
-                    functions.push(el.value);
-                }
-            }
-            initialize(functions);
+            initializeInputs();
             run(currentView[0], currentView[1], currentView[2], currentView[3]);
             if(!expandTimeout) {
                 expand();
@@ -253,16 +313,85 @@ init().then(() => {
         }
     })
 
-    //toggle mode between polar and cartesian when checkbox is changed
-    let mode = document.getElementById("mode");
-    mode.addEventListener("click", () => {
-        toggle_mode();
-        run(currentView[0], currentView[1], currentView[2], currentView[3]);
-        if(!mode.checked) {
-            //if in cartesian mode, begin expanding caches
+    /*a checkbox after each function input to graph that function in polar coordinates, followed by inputs for the range of θ it is graphed
+    over, which are shown while it is checked*/
+    let thetaInputs = [];
+    let derivativeTexts = [];
+    for (let i = 0; i < inputs.length; ++i) {
+        let polar = document.createElement("input");
+        polar.type = "checkbox";
+        polar.className = "polar-mode";
+        polar.title = "Polar mode";
+        polar.addEventListener("click", () => {
+            keepDrawing = false;
+            inputs[i].parentElement.classList.toggle("polar", polar.checked);
+            //only this function's graph is recalculated
+            set_mode(i, polar.checked ? CoordinateMode.Polar : CoordinateMode.Cartesian);
             if(!expandTimeout) {
                 expand();
             }
+        });
+        let start = document.createElement("input");
+        let end = document.createElement("input");
+        for (let el of [start, end]) {
+            el.type = "number";
+            el.step = "any";
+            el.className = "theta-range";
+            //an empty range graphs the function over its default range, which is shown as a placeholder
+            el.addEventListener("change", () => {
+                keepDrawing = false;
+                let thetaStart = parseFloat(start.value);
+                let thetaEnd = parseFloat(end.value);
+                if (start.value === "" && end.value === "") {
+                    clear_theta_range(i);
+                }
+                else if (!isNaN(thetaStart) && !isNaN(thetaEnd)) {
+                    set_theta_range(i, thetaStart, thetaEnd);
+                }
+            });
+        }
+        start.title = "θ from";
+        end.title = "θ to";
+        //the shape of the markers drawn when the input is a list of points
+        let marker = document.createElement("select");
+        marker.className = "marker-shape";
+        marker.title = "Marker shape";
+        for (let [shape, label] of [["Circle", "●"], ["Square", "■"], ["Triangle", "▲"], ["Cross", "✕"]]) {
+            marker.add(new Option(label, shape));
+        }
+        marker.addEventListener("change", () => {
+            keepDrawing = false;
+            set_marker(i, MarkerShape[marker.value]);
+        });
+        //a checkbox to draw the function's first and second derivatives under it, followed by the derivatives, which are shown while it is checked
+        let derivative = document.createElement("input");
+        derivative.type = "checkbox";
+        derivative.className = "derivative-mode";
+        derivative.title = "Show derivatives";
+        derivative.addEventListener("click", () => {
+            keepDrawing = false;
+            inputs[i].parentElement.classList.toggle("derivatives", derivative.checked);
+            set_derivatives(i, derivative.checked);
+        });
+        let derivativeText = document.createElement("span");
+        derivativeText.className = "derivative-text";
+        inputs[i].after(polar, start, end, marker, derivative, derivativeText);
+        thetaInputs.push([start, end]);
+        derivativeTexts.push(derivativeText);
+    }
+    //function to show the first and second derivatives of each function, as they were simplified
+    let updateDerivativeText = (result) => {
+        for (let i = 0; i < derivativeTexts.length; ++i) {
+            let [first, second] = result[i].derivatives;
+            derivativeTexts[i].textContent = (first ? "f′ = " + first : "") + (second ? ", f″ = " + second : "");
         }
-    });
+    }
+    //function to show the default range of θ of each function in its range inputs
+    let updateThetaPlaceholders = () => {
+        for (let i = 0; i < thetaInputs.length; ++i) {
+            let range = get_theta_range(i);
+            thetaInputs[i][0].placeholder = range.length ? "θ " + range[0].toFixed(2) : "θ from";
+            thetaInputs[i][1].placeholder = range.length ? range[1].toFixed(2) : "θ to";
+        }
+    }
 });
\ No newline at end of file
@@ -1,159 +1,1116 @@
 use std::f64::consts::{PI, E};
 
-use crate::{scanner::TokenType, parser::ASTNodeType};
+use crate::{dual::{self, Dual}, error::EvalError, interval::{self, Bounds}, scanner::{Span, TokenType}, parser::{ASTNode, ASTNodeType}, simplify::optimize};
+
+//default value of a parameter that hasn't been set yet
+pub const DEFAULT_PARAMETER_VALUE: f64 = 1.0;
+//largest whole number power calculated by repeated multiplication rather than as a power of any number (see compile)
+const MAX_INTEGER_POWER: f64 = 64.0;
 
 pub struct Calculator {
     instructions: Vec<CalculatorInstruction>,
-    stack: Vec<f64>,
-    delta: f64
+    //operand holding the value of the expression once every instruction has run
+    result: Operand,
+    //values calculated by the instructions - there is a slot for each, allocated when the expression is compiled, so nothing is pushed or popped while calculating
+    slots: Vec<f64>,
+    //names of the parameters used by the expression, and their current values - operands refer to parameters by index into these
+    parameter_names: Vec<String>,
+    parameter_values: Vec<f64>,
+    //buffers reused by each call to calculate_interval, so that bounding the expression doesn't allocate once they have grown to fit it
+    scratch: IntervalScratch
+}
+
+//the paths followed by calculate_interval, and copies of the slots that are no longer on any path, kept to be reused by later paths
+#[derive(Default)]
+struct IntervalScratch {
+    paths: Vec<Option<BoundsPath>>,
+    spare: Vec<Vec<Bounds>>
 }
 
 impl Calculator {
-    fn new(v: Vec<CalculatorInstruction>, delta: f64) -> Calculator {
-        Calculator { instructions: v, stack: Vec::new(), delta }
+    /*function to create a calculator that runs compiled instructions, once they have been checked (see slots_are_valid) - span is the span of
+    the expression they were compiled from, where an error is reported if they aren't valid*/
+    fn new(compiled: Compilation, result: Operand, span: Span) -> Result<Calculator, EvalError> {
+        if !slots_are_valid(&compiled.instructions, result, compiled.slot_count, compiled.parameters.len()) { return Err(EvalError::InvalidInstructions { span }); }
+        let parameter_values = vec![DEFAULT_PARAMETER_VALUE; compiled.parameters.len()];
+        Ok(Calculator { instructions: compiled.instructions, result, slots: vec![0.0; compiled.slot_count], parameter_names: compiled.parameters, parameter_values, scratch: IntervalScratch::default() })
     }
 
-    //function to calculate f(x) for the expression represented by this Calculator for a given x - returns None if f is undefined at x
+    //function to get the names of the parameters used by the expression, in order of first use
+    pub fn parameters(&self) -> &[String] {
+        &self.parameter_names
+    }
+
+    //function to set the value of a parameter - returns true if the expression uses the parameter, and so its values may have changed
+    pub fn set_parameter(&mut self, name: &str, value: f64) -> bool {
+        match self.parameter_names.iter().position(|parameter| parameter == name) {
+            Some(i) => {
+                self.parameter_values[i] = value;
+                true
+            }
+            None => false
+        }
+    }
+
+    /*function to calculate f(x) for the expression represented by this Calculator for a given x - returns None if f is undefined at x. An
+    expression of y alone (see definition.rs) is calculated at y = x*/
     pub fn calculate(&mut self, x: f64) -> Option<f64> {
-        for instruction in self.instructions.iter() {
+        self.calculate_point(x, x)
+    }
+
+    //function to calculate F(x, y) for an expression of both variables, as plotted by an implicit curve - returns None if F is undefined at (x, y)
+    pub fn calculate_point(&mut self, x: f64, y: f64) -> Option<f64> {
+        let Calculator { instructions, result, slots, parameter_values, .. } = self;
+        let value = |slots: &[f64], operand: &Operand| match *operand {
+            Operand::Num(num) => num,
+            Operand::X => x,
+            Operand::Y => y,
+            Operand::Parameter(i) => parameter_values[i],
+            Operand::Slot(i) => slots[i]
+        };
+        let mut pc = 0;
+        while pc < instructions.len() {
+            let instruction = &instructions[pc];
+            pc += 1;
             match instruction {
-                CalculatorInstruction::Push(val) => {
-                    match val {
-                        CalculatorValue::Num(num) => self.stack.push(*num),
-                        CalculatorValue::Variable(_) => self.stack.push(x)
-                    }
+                CalculatorInstruction::Calculate(calculation, slot) => {
+                    slots[*slot] = match calculation {
+                        Calculation::Unary(operation, a) => {
+                            let a = value(slots, a);
+                            match operation {
+                                UnaryOperation::Neg => -a,
+                                UnaryOperation::Powi(n) => a.powi(*n),
+                                UnaryOperation::Factorial => factorial(a)?,
+                                UnaryOperation::Function(function) => function.apply(&[a])?
+                            }
+                        },
+                        Calculation::Binary(operation, a, b) => {
+                            let (a, b) = (value(slots, a), value(slots, b));
+                            match operation {
+                                BinaryOperation::Add => a + b,
+                                BinaryOperation::Sub => a - b,
+                                BinaryOperation::Mul => a * b,
+                                //return None on division by zero
+                                BinaryOperation::Div => {
+                                    if b == 0.0 { return None; }
+                                    a / b
+                                },
+                                BinaryOperation::Exp => {
+                                    let val = a.powf(b);
+                                    if val.is_nan() { return None; }
+                                    val
+                                },
+                                BinaryOperation::Mod => floored_mod(a, b)?,
+                                BinaryOperation::Compare(operator) => compare(operator, a, b),
+                                BinaryOperation::Function(function) => function.apply(&[a, b])?
+                            }
+                        }
+                    };
                 },
-                CalculatorInstruction::Add => {
-                    let right = self.stack.pop().unwrap();
-                    let left = self.stack.pop().unwrap();
-                    self.stack.push(left + right);
+                CalculatorInstruction::Store(operand, slot) => slots[*slot] = value(slots, operand),
+                CalculatorInstruction::Jump(skip) => pc += skip,
+                CalculatorInstruction::JumpUnless(condition, skip) => {
+                    if value(slots, condition) == 0.0 { pc += skip; }
                 },
-                CalculatorInstruction::Sub => {
-                    let right = self.stack.pop().unwrap();
-                    let left = self.stack.pop().unwrap();
-                    self.stack.push(left - right);
+                CalculatorInstruction::Undefined => return None
+            }
+        }
+        Some(value(slots, result))
+    }
+
+    /*function to calculate f(x) and its derivative f′(x) for a given x, by automatic differentiation (see dual.rs) - returns None if f is
+    undefined at x, or its derivative is undefined or infinite there. As in calculate, an expression of y alone is differentiated with
+    respect to y*/
+    pub fn calculate_derivative(&self, x: f64) -> Option<(f64, f64)> {
+        let mut slots = vec![Dual::constant(0.0); self.slots.len()];
+        let value = |slots: &[Dual], operand: &Operand| match *operand {
+            Operand::Num(num) => Dual::constant(num),
+            Operand::X | Operand::Y => Dual::new(x, 1.0),
+            Operand::Parameter(i) => Dual::constant(self.parameter_values[i]),
+            Operand::Slot(i) => slots[i]
+        };
+        let mut pc = 0;
+        while pc < self.instructions.len() {
+            let instruction = &self.instructions[pc];
+            pc += 1;
+            match instruction {
+                CalculatorInstruction::Calculate(calculation, slot) => {
+                    slots[*slot] = match calculation {
+                        Calculation::Unary(operation, a) => {
+                            let a = value(&slots, a);
+                            match operation {
+                                UnaryOperation::Neg => Dual::new(-a.value, -a.derivative),
+                                UnaryOperation::Powi(n) => dual::pow(a, Dual::constant(*n as f64))?,
+                                UnaryOperation::Factorial => dual::factorial(a)?,
+                                UnaryOperation::Function(function) => dual::apply_function(*function, &[a])?
+                            }
+                        },
+                        Calculation::Binary(operation, a, b) => {
+                            let (a, b) = (value(&slots, a), value(&slots, b));
+                            match operation {
+                                BinaryOperation::Add => dual::add(a, b),
+                                BinaryOperation::Sub => dual::sub(a, b),
+                                BinaryOperation::Mul => dual::mul(a, b),
+                                BinaryOperation::Div => dual::div(a, b)?,
+                                BinaryOperation::Exp => dual::pow(a, b)?,
+                                BinaryOperation::Mod => dual::modulo(a, b)?,
+                                //a condition only changes where it jumps between holding and not, so is flat everywhere else
+                                BinaryOperation::Compare(operator) => Dual::constant(compare(operator, a.value, b.value)),
+                                BinaryOperation::Function(function) => dual::apply_function(*function, &[a, b])?
+                            }
+                        }
+                    };
                 },
-                CalculatorInstruction::Mul => {
-                    let right = self.stack.pop().unwrap();
-                    let left = self.stack.pop().unwrap();
-                    self.stack.push(left * right);
+                CalculatorInstruction::Store(operand, slot) => slots[*slot] = value(&slots, operand),
+                CalculatorInstruction::Jump(skip) => pc += skip,
+                CalculatorInstruction::JumpUnless(condition, skip) => {
+                    if value(&slots, condition).value == 0.0 { pc += skip; }
                 },
-                CalculatorInstruction::Div => {
-                    let right = self.stack.pop().unwrap();
-                    let left = self.stack.pop().unwrap();
-                    //return None on division by zero
-                    if right.abs() <= self.delta { return None; }
-                    self.stack.push(left / right);
+                CalculatorInstruction::Undefined => return None
+            }
+        }
+        let result = value(&slots, &self.result);
+        if !result.value.is_finite() || !result.derivative.is_finite() { return None; }
+        Some((result.value, result.derivative))
+    }
+
+    /*function to bound f(x) for every x from start to end (see interval.rs) - returns None if f is undefined everywhere in the range. An
+    expression of y alone is bounded over the same range of y, as in calculate. Where a condition may go either way in the range, both
+    branches are followed, each with its own copy of the slots, and where the branches meet again their slots are merged into bounds on
+    both, so that each instruction is only run once however many conditions are undecided. Jumps only go forward, so every path to an
+    instruction has been followed by the time it is reached*/
+    pub fn calculate_interval(&mut self, start: f64, end: f64) -> Option<Bounds> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        let bounds = self.bound_paths(&mut scratch, Bounds::new(start, end));
+        self.scratch = scratch;
+        bounds
+    }
+
+    //function to follow every path through the instructions with x bounded by x, for calculate_interval
+    fn bound_paths(&self, scratch: &mut IntervalScratch, x: Bounds) -> Option<Bounds> {
+        let IntervalScratch { paths, spare } = scratch;
+        //the slots, and what the conditions tested so far say about where the result is defined and continuous, on the paths to each instruction
+        paths.clear();
+        paths.resize(self.instructions.len() + 1, None);
+        let mut slots = spare.pop().unwrap_or_default();
+        slots.clear();
+        slots.resize(self.slots.len(), Bounds::point(0.0));
+        paths[0] = Some((slots, Bounds::point(1.0)));
+        //whether some path is undefined everywhere in the range, so that the result is undefined somewhere in it
+        let mut undefined = false;
+        for pc in 0..self.instructions.len() {
+            let Some((mut slots, mut conditions)) = paths[pc].take() else { continue; };
+            let next = match &self.instructions[pc] {
+                CalculatorInstruction::Calculate(calculation, slot) => {
+                    let Some(bounds) = self.bound_calculation(calculation, &slots, x) else {
+                        undefined = true;
+                        spare.push(slots);
+                        continue;
+                    };
+                    slots[*slot] = bounds;
+                    pc + 1
                 },
-                CalculatorInstruction::Exp => {
-                    let power = self.stack.pop().unwrap();
-                    let base = self.stack.pop().unwrap();
-                    let val = base.powf(power);
-                    if val.is_nan() { return None; }
-                    self.stack.push(val);
+                CalculatorInstruction::Store(operand, slot) => {
+                    slots[*slot] = self.bound_operand(operand, &slots, x);
+                    pc + 1
                 },
-                CalculatorInstruction::ApplyFunc(f) => {
-                    let arg = self.stack.pop().unwrap();
-                    if let TokenType::FunctionName(function_name) = f { 
-                        match function_name.as_str() {
-                            "sin" => self.stack.push(arg.sin()),
-                            "cos" => self.stack.push(arg.cos()),
-                            "tan" => {
-                                if arg.cos().abs() <= self.delta { return None };
-                                self.stack.push(arg.tan());
-                            },
-                            "log" => {
-                                let val = arg.log10();
-                                if val == std::f64::NEG_INFINITY { return None; }
-                                self.stack.push(val);
-                            },
-                            "ln" => {
-                                let val = arg.ln();
-                                if val == std::f64::NEG_INFINITY { return None; }
-                                self.stack.push(val);
-                            },
-                            "sqrt" => {
-                                if arg < 0.0 { return None; }
-                                self.stack.push(arg.sqrt()); 
-                            }
-                            "abs" => {
-                                self.stack.push(arg.abs());
-                            }
-                            _ => ()
+                CalculatorInstruction::Jump(skip) => pc + 1 + skip,
+                CalculatorInstruction::JumpUnless(condition, skip) => {
+                    let condition = self.bound_operand(condition, &slots, x);
+                    conditions = conditions.inherit(condition);
+                    if condition.lo == 0.0 && condition.hi == 0.0 { pc + 1 + skip }
+                    else {
+                        if condition.contains(0.0) {
+                            let mut copy = spare.pop().unwrap_or_default();
+                            copy.clone_from(&slots);
+                            merge_path(&mut paths[pc + 1 + skip], (copy, conditions), spare);
                         }
+                        pc + 1
                     }
+                },
+                CalculatorInstruction::Undefined => {
+                    undefined = true;
+                    spare.push(slots);
+                    continue;
+                }
+            };
+            merge_path(&mut paths[next], (slots, conditions), spare);
+        }
+        let (slots, conditions) = paths.pop().flatten()?;
+        let bounds = self.bound_operand(&self.result, &slots, x).inherit(conditions);
+        spare.push(slots);
+        Some(if undefined { bounds.partial() } else { bounds })
+    }
+
+    //function to bound an operand, given bounds on the slots and on x
+    fn bound_operand(&self, operand: &Operand, slots: &[Bounds], x: Bounds) -> Bounds {
+        match *operand {
+            Operand::Num(num) => Bounds::point(num),
+            Operand::X | Operand::Y => x,
+            Operand::Parameter(i) => Bounds::point(self.parameter_values[i]),
+            Operand::Slot(i) => slots[i]
+        }
+    }
+
+    //function to bound the result of a calculation, given bounds on the slots and on x - None if it is undefined everywhere in the range
+    fn bound_calculation(&self, calculation: &Calculation, slots: &[Bounds], x: Bounds) -> Option<Bounds> {
+        let value = |operand: &Operand| self.bound_operand(operand, slots, x);
+        let bounds = match calculation {
+            Calculation::Unary(operation, a) => {
+                let a = value(a);
+                match operation {
+                    UnaryOperation::Neg => interval::mul(Bounds::point(-1.0), a),
+                    UnaryOperation::Powi(n) => interval::pow(a, Bounds::point(*n as f64))?,
+                    UnaryOperation::Factorial => interval::factorial_bounds(a)?,
+                    UnaryOperation::Function(function) => interval::apply_function(*function, &[a])?
+                }
+            },
+            Calculation::Binary(operation, a, b) => {
+                let (a, b) = (value(a), value(b));
+                match operation {
+                    BinaryOperation::Add => interval::add(a, b),
+                    BinaryOperation::Sub => interval::sub(a, b),
+                    BinaryOperation::Mul => interval::mul(a, b),
+                    BinaryOperation::Div => interval::div(a, b)?,
+                    BinaryOperation::Exp => interval::pow(a, b)?,
+                    BinaryOperation::Mod => interval::modulo(a, b)?,
+                    BinaryOperation::Compare(operator) => interval::compare(operator, a, b),
+                    BinaryOperation::Function(function) => interval::apply_function(*function, &[a, b])?
                 }
             }
+        };
+        Some(bounds)
+    }
+}
+
+//slots and conditions on the paths to an instruction, when bounding an expression over a range (see Calculator::calculate_interval)
+type BoundsPath = (Vec<Bounds>, Bounds);
+
+/*function to merge another path into the paths to an instruction - each slot is bounded by its bounds on either path. A slot that is only
+stored on one of the paths is never read after they meet, so its merged bounds don't matter. The merged path's slots are kept in spare*/
+fn merge_path(paths: &mut Option<BoundsPath>, path: BoundsPath, spare: &mut Vec<Vec<Bounds>>) {
+    match paths {
+        Some((slots, conditions)) => {
+            slots.iter_mut().zip(&path.0).for_each(|(a, b)| *a = a.union(*b));
+            *conditions = conditions.inherit(path.1);
+            spare.push(path.0);
+        },
+        None => *paths = Some(path)
+    }
+}
+
+//function to compare two values - 1 if the comparison holds, and 0 otherwise
+pub fn compare(operator: &TokenType, left: f64, right: f64) -> f64 {
+    let holds = match operator {
+        TokenType::Less => left < right,
+        TokenType::LessEqual => left <= right,
+        TokenType::Greater => left > right,
+        _ => left >= right
+    };
+    if holds { 1.0 } else { 0.0 }
+}
+
+//built in functions, as typed opcodes, so that a compiled expression never looks a function up by its name while calculating
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Function {
+    Sin, Cos, Tan, Log, Ln, Sqrt, Abs, Asin, Acos, Atan,
+    Sec, Csc, Cot, Sinh, Cosh, Tanh, Asinh, Acosh, Atanh, Exp,
+    Floor, Ceil, Round, Sign, Cbrt, Max, Min, Atan2, Root, Mod
+}
+
+//every built in function, in the order of RECOGNIZED_FUNCTIONS
+const FUNCTIONS: [Function; 30] = [Function::Sin, Function::Cos, Function::Tan, Function::Log, Function::Ln, Function::Sqrt, Function::Abs, Function::Asin,
+                                   Function::Acos, Function::Atan, Function::Sec, Function::Csc, Function::Cot, Function::Sinh, Function::Cosh, Function::Tanh,
+                                   Function::Asinh, Function::Acosh, Function::Atanh, Function::Exp, Function::Floor, Function::Ceil, Function::Round, Function::Sign,
+                                   Function::Cbrt, Function::Max, Function::Min, Function::Atan2, Function::Root, Function::Mod];
+
+impl Function {
+    //function to get the built in function with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<Function> {
+        FUNCTIONS.iter().copied().find(|function| function.name() == name)
+    }
+
+    //function to get the name the function is written with
+    pub fn name(self) -> &'static str {
+        match self {
+            Function::Sin => "sin",
+            Function::Cos => "cos",
+            Function::Tan => "tan",
+            Function::Log => "log",
+            Function::Ln => "ln",
+            Function::Sqrt => "sqrt",
+            Function::Abs => "abs",
+            Function::Asin => "asin",
+            Function::Acos => "acos",
+            Function::Atan => "atan",
+            Function::Sec => "sec",
+            Function::Csc => "csc",
+            Function::Cot => "cot",
+            Function::Sinh => "sinh",
+            Function::Cosh => "cosh",
+            Function::Tanh => "tanh",
+            Function::Asinh => "asinh",
+            Function::Acosh => "acosh",
+            Function::Atanh => "atanh",
+            Function::Exp => "exp",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Round => "round",
+            Function::Sign => "sign",
+            Function::Cbrt => "cbrt",
+            Function::Max => "max",
+            Function::Min => "min",
+            Function::Atan2 => "atan2",
+            Function::Root => "root",
+            Function::Mod => "mod"
         }
-        Some(self.stack.pop().unwrap())
     }
+
+    /*function to apply the function to its arguments - returns None outside of the function's domain. Poles (e.g. tan at pi/2) are found
+    by bounding the function over a range instead (see interval.rs). Expects the number of arguments to have been checked by the parser*/
+    pub fn apply(self, args: &[f64]) -> Option<f64> {
+        let arg = args[0];
+        let val = match self {
+            Function::Sin => arg.sin(),
+            Function::Cos => arg.cos(),
+            Function::Tan => {
+                if arg.cos() == 0.0 { return None; }
+                arg.tan()
+            },
+            Function::Sec => {
+                if arg.cos() == 0.0 { return None; }
+                1.0 / arg.cos()
+            },
+            Function::Csc => {
+                if arg.sin() == 0.0 { return None; }
+                1.0 / arg.sin()
+            },
+            Function::Cot => {
+                if arg.sin() == 0.0 { return None; }
+                arg.cos() / arg.sin()
+            },
+            Function::Asin => {
+                if arg.abs() > 1.0 { return None; }
+                arg.asin()
+            },
+            Function::Acos => {
+                if arg.abs() > 1.0 { return None; }
+                arg.acos()
+            },
+            Function::Atan => arg.atan(),
+            Function::Sinh => arg.sinh(),
+            Function::Cosh => arg.cosh(),
+            Function::Tanh => arg.tanh(),
+            Function::Asinh => arg.asinh(),
+            Function::Acosh => {
+                if arg < 1.0 { return None; }
+                arg.acosh()
+            },
+            Function::Atanh => {
+                if arg.abs() >= 1.0 { return None; }
+                arg.atanh()
+            },
+            Function::Log if args.len() == 2 => {
+                //log(b, x) is the base b logarithm of x
+                let (base, arg) = (args[0], args[1]);
+                if arg <= 0.0 || base <= 0.0 || base == 1.0 { return None; }
+                arg.ln() / base.ln()
+            },
+            Function::Log => {
+                if arg <= 0.0 { return None; }
+                arg.log10()
+            },
+            Function::Ln => {
+                if arg <= 0.0 { return None; }
+                arg.ln()
+            },
+            Function::Exp => arg.exp(),
+            Function::Sqrt => {
+                if arg < 0.0 { return None; }
+                arg.sqrt()
+            },
+            Function::Cbrt => arg.cbrt(),
+            Function::Abs => arg.abs(),
+            Function::Floor => arg.floor(),
+            Function::Ceil => arg.ceil(),
+            Function::Round => arg.round(),
+            //unlike f64::signum, the sign of 0 is 0
+            Function::Sign => if arg == 0.0 { 0.0 } else { arg.signum() },
+            Function::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Function::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+            //atan2(y, x) is the angle of the point (x, y)
+            Function::Atan2 => args[0].atan2(args[1]),
+            Function::Root => {
+                //root(n, x) is the nth root of x - negative x only has a real root when n is an odd integer
+                let (n, arg) = (args[0], args[1]);
+                if n == 0.0 { return None; }
+                if arg < 0.0 {
+                    if n.fract() != 0.0 || n % 2.0 == 0.0 { return None; }
+                    -(-arg).powf(1.0 / n)
+                }
+                else { arg.powf(1.0 / n) }
+            },
+            Function::Mod => floored_mod(args[0], args[1])?,
+        };
+        //values that overflow (e.g. exp or sinh of large arguments) can't be graphed
+        if !val.is_finite() { return None; }
+        Some(val)
+    }
+}
+
+//function to apply the built in function with the given name to its arguments, as for Function::apply - None if there is no such function
+pub fn apply_function(name: &str, args: &[f64]) -> Option<f64> {
+    Function::from_name(name)?.apply(args)
+}
+
+//function to calculate x mod m, with the result taking the sign of m (so -1 mod 3 is 2) - undefined when m is 0
+pub fn floored_mod(x: f64, m: f64) -> Option<f64> {
+    if m == 0.0 { return None; }
+    Some(x - m * (x / m).floor())
+}
+
+/*function to calculate x! - exact for integers, and gamma(x + 1) otherwise, so that the graph of x! is continuous. Undefined at the negative 
+integers (the poles of gamma) and where the result overflows*/
+pub fn factorial(x: f64) -> Option<f64> {
+    if x < 0.0 && x.fract() == 0.0 { return None; }
+    let val = if x >= 0.0 && x.fract() == 0.0 && x <= 170.0 {
+        (1..=x as u32).fold(1.0, |product, n| product * n as f64)
+    }
+    else { gamma(x + 1.0) };
+    if !val.is_finite() { return None; }
+    Some(val)
+}
+
+//function to approximate the gamma function using the Lanczos approximation (g = 7), with the reflection formula for x < 0.5
+fn gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8, 771.323_428_777_653_1,
+                                    -176.615_029_162_140_6, 12.507_343_278_686_905, -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6,
+                                    1.505_632_735_149_311_6e-7];
+    if x < 0.5 {
+        return PI / ((PI * x).sin() * gamma(1.0 - x));
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..].iter().enumerate().fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+    (2.0 * PI).sqrt() * t.powf(x + 0.5) * (-t).exp() * sum
 }
 
-//function to take an AST node and produce a vector of instructions for the Calculator to run
-fn generate_instructions(expression: ASTNodeType) -> Vec<CalculatorInstruction> {
-    let mut instructions : Vec<CalculatorInstruction> = Vec::new();
-    match expression {
-        ASTNodeType::BinaryExpression(operator,left , right) => {
-            instructions.append(&mut generate_instructions(*left));
-            instructions.append(&mut generate_instructions(*right));
-            match operator {
-                TokenType::Add => instructions.push(CalculatorInstruction::Add),
-                TokenType::Sub => instructions.push(CalculatorInstruction::Sub),
-                TokenType::Mul => instructions.push(CalculatorInstruction::Mul),
-                TokenType::Div => instructions.push(CalculatorInstruction::Div),
-                TokenType::Exp => instructions.push(CalculatorInstruction::Exp),
-                _ => ()
+/*state kept while compiling an expression into instructions - each calculation is stored in a slot of its own, and a calculation that has
+already been made where it is needed is reused rather than made again, so that an expression such as sin(x)^2 + sin(x) cos(x) only
+calculates sin(x) once. A calculation made inside a branch (of a piecewise expression, or the right side of and or or) may not have been
+made when the branch isn't taken, so is only reused inside that branch*/
+struct Compilation {
+    instructions: Vec<CalculatorInstruction>,
+    slot_count: usize,
+    parameters: Vec<String>,
+    //calculations that have been made wherever the instruction being compiled runs, with the slots holding their results
+    available: Vec<(Calculation, usize)>
+}
+
+impl Compilation {
+    fn new_slot(&mut self) -> usize {
+        self.slot_count += 1;
+        self.slot_count - 1
+    }
+
+    //function to produce an operand holding the result of a calculation, reusing the slot of the same calculation made earlier if there is one
+    fn calculate(&mut self, calculation: Calculation) -> Operand {
+        if let Some((_, slot)) = self.available.iter().find(|(available, _)| *available == calculation) { return Operand::Slot(*slot); }
+        let slot = self.new_slot();
+        self.available.push((calculation.clone(), slot));
+        self.instructions.push(CalculatorInstruction::Calculate(calculation, slot));
+        Operand::Slot(slot)
+    }
+
+    /*function to compile an expression that only runs in a branch, storing its value in slot - returns the instructions of the branch
+    separately, so that the jumps around it can be worked out from its length*/
+    fn compile_branch(&mut self, expression: ASTNode, slot: usize) -> Result<Vec<CalculatorInstruction>, EvalError> {
+        let (outer, available) = (std::mem::take(&mut self.instructions), self.available.len());
+        let value = compile(expression, self);
+        self.available.truncate(available);
+        let mut branch = std::mem::replace(&mut self.instructions, outer);
+        branch.push(CalculatorInstruction::Store(value?, slot));
+        Ok(branch)
+    }
+}
+
+//function to compile an AST node into instructions that calculate it, added to the compilation - returns the operand holding its value
+fn compile(expression: ASTNode, compilation: &mut Compilation) -> Result<Operand, EvalError> {
+    let span = expression.span;
+    let operand = match expression.node_type {
+        ASTNodeType::BinaryExpression(operator, left, right) => {
+            let (left, right) = (compile(*left, compilation)?, compile(*right, compilation)?);
+            //whole number powers are calculated by repeated multiplication, which is much quicker than a power of any number
+            if let (TokenType::Exp, Operand::Num(n)) = (&operator, right) {
+                if n.fract() == 0.0 && n.abs() <= MAX_INTEGER_POWER {
+                    return Ok(compilation.calculate(Calculation::Unary(UnaryOperation::Powi(n as i32), left)));
+                }
+            }
+            let operation = match operator {
+                TokenType::Add => BinaryOperation::Add,
+                TokenType::Sub => BinaryOperation::Sub,
+                TokenType::Mul => BinaryOperation::Mul,
+                TokenType::Div => BinaryOperation::Div,
+                TokenType::Exp => BinaryOperation::Exp,
+                TokenType::Mod => BinaryOperation::Mod,
+                other => return Err(EvalError::UnsupportedOperator { operator: other.to_string(), span })
             };
+            compilation.calculate(Calculation::Binary(operation, left, right))
         }
-        ASTNodeType::UnaryExpression(operator,expression ) => {
-            instructions.push(CalculatorInstruction::Push(CalculatorValue::Num(-1.0)));
-            instructions.append(&mut generate_instructions(*expression));
-            match operator {
-                TokenType::Sub => instructions.push(CalculatorInstruction::Mul),
-                _ => ()
+        ASTNodeType::UnaryExpression(operator, expression) => {
+            let operation = match operator {
+                TokenType::Sub => UnaryOperation::Neg,
+                TokenType::Factorial => UnaryOperation::Factorial,
+                other => return Err(EvalError::UnsupportedOperator { operator: other.to_string(), span })
             };
+            let operand = compile(*expression, compilation)?;
+            compilation.calculate(Calculation::Unary(operation, operand))
         }
-        ASTNodeType::FunctionExpression(function_name, argument) => {
-            instructions.append(&mut generate_instructions(*argument));
-            instructions.push(CalculatorInstruction::ApplyFunc(function_name));
+        ASTNodeType::FunctionExpression(function_name, arguments) => {
+            let Some(function) = Function::from_name(&function_name.to_string()) else {
+                return Err(EvalError::UnknownFunction { name: function_name.to_string(), span });
+            };
+            let arguments = arguments.into_iter().map(|argument| compile(argument, compilation)).collect::<Result<Vec<_>, _>>()?;
+            match arguments[..] {
+                [argument] => compilation.calculate(Calculation::Unary(UnaryOperation::Function(function), argument)),
+                /*max and min of more than two arguments are found two at a time, so that no calculation has more than two operands, and nothing
+                is collected while calculating - the parser only allows more than two arguments for those*/
+                _ => arguments[1..].iter().fold(arguments[0], |a, b| {
+                    compilation.calculate(Calculation::Binary(BinaryOperation::Function(function), a, *b))
+                })
+            }
+        }
+        /*conditions evaluate to 1 where they hold and 0 elsewhere - an input that is an inequality is plotted as a region instead (see 
+        definition.rs)*/
+        ASTNodeType::InequalityExpression(operator, left, right) => {
+            let (left, right) = (compile(*left, compilation)?, compile(*right, compilation)?);
+            compilation.calculate(Calculation::Binary(BinaryOperation::Compare(operator), left, right))
         }
+        //and and or only evaluate their right side when the left side doesn't already decide the result
+        ASTNodeType::LogicalExpression(operator, left, right) => {
+            let left = compile(*left, compilation)?;
+            let slot = compilation.new_slot();
+            let mut right = compilation.compile_branch(*right, slot)?;
+            let instructions = &mut compilation.instructions;
+            if operator == TokenType::And {
+                instructions.push(CalculatorInstruction::JumpUnless(left, right.len() + 1));
+                instructions.append(&mut right);
+                instructions.push(CalculatorInstruction::Jump(1));
+                instructions.push(CalculatorInstruction::Store(Operand::Num(0.0), slot));
+            }
+            else {
+                instructions.push(CalculatorInstruction::JumpUnless(left, 2));
+                instructions.push(CalculatorInstruction::Store(Operand::Num(1.0), slot));
+                instructions.push(CalculatorInstruction::Jump(right.len()));
+                instructions.append(&mut right);
+            }
+            Operand::Slot(slot)
+        }
+        /*each branch tests its condition, and either calculates its value and jumps past the remaining branches, or jumps to the next branch.
+        Every branch stores its value in the same slot. A condition is only tested when the conditions before it don't hold, so is compiled as
+        a branch of them. The instructions are put together from the last branch back, so that the length of the remaining branches is known*/
+        ASTNodeType::PiecewiseExpression(branches, default) => {
+            let slot = compilation.new_slot();
+            let (outer, available) = (std::mem::take(&mut compilation.instructions), compilation.available.len());
+            let mut compiled = Vec::new();
+            for (condition, value) in branches {
+                let condition = compile(condition, compilation)?;
+                let value = compilation.compile_branch(value, slot)?;
+                compiled.push((std::mem::take(&mut compilation.instructions), condition, value));
+            }
+            let mut remaining = match default {
+                Some(default) => compilation.compile_branch(*default, slot)?,
+                None => vec![CalculatorInstruction::Undefined]
+            };
+            compilation.available.truncate(available);
+            for (mut instructions, condition, mut value) in compiled.into_iter().rev() {
+                instructions.push(CalculatorInstruction::JumpUnless(condition, value.len() + 1));
+                instructions.append(&mut value);
+                instructions.push(CalculatorInstruction::Jump(remaining.len()));
+                instructions.append(&mut remaining);
+                remaining = instructions;
+            }
+            compilation.instructions = outer;
+            compilation.instructions.append(&mut remaining);
+            Operand::Slot(slot)
+        }
+        //points are graphed as markers, with a calculator for each coordinate (see definition.rs)
+        ASTNodeType::PointExpression(_, _) | ASTNodeType::ListExpression(_) => return Err(EvalError::UnexpectedPoint { span }),
         ASTNodeType::AtomicExpression(expression) => {
             match expression {
-                TokenType::NumLiteral(num) => instructions.push(CalculatorInstruction::Push(CalculatorValue::Num(num))),
-                TokenType::Variable(_) => instructions.push(CalculatorInstruction::Push(CalculatorValue::Variable(expression))),
+                TokenType::NumLiteral(num) => Operand::Num(num),
+                TokenType::Variable(name) if name == "y" => Operand::Y,
+                TokenType::Variable(_) => Operand::X,
+                TokenType::Parameter(name) => {
+                    let parameters = &mut compilation.parameters;
+                    match parameters.iter().position(|parameter| *parameter == name) {
+                        Some(i) => Operand::Parameter(i),
+                        None => {
+                            parameters.push(name);
+                            Operand::Parameter(parameters.len() - 1)
+                        }
+                    }
+                }
                 TokenType::Constant(c) => {
                     match c.as_str() {
-                        "pi" => instructions.push(CalculatorInstruction::Push(CalculatorValue::Num(PI))),
-                        "e" => instructions.push(CalculatorInstruction::Push(CalculatorValue::Num(E))),
-                        _ => ()
+                        "pi" => Operand::Num(PI),
+                        "e" => Operand::Num(E),
+                        _ => return Err(EvalError::UnknownConstant { name: c, span })
                     }
                 }
-                _ => ()
+                other => return Err(EvalError::UnsupportedOperator { operator: other.to_string(), span })
             }
         }
+    };
+    Ok(operand)
+}
+
+/*function to create a new calculator and compile instructions for it given an AST, once it has been optimized (see simplify.rs) - the
+parameters are numbered in the order they are first used in the AST as it was given, since optimizing reorders terms*/
+pub fn generate_calculator(expression: ASTNode) -> Result<Calculator, EvalError> {
+    let mut parameters = Vec::new();
+    collect_parameters(&expression, &mut parameters);
+    let span = expression.span;
+    let mut compilation = Compilation { instructions: Vec::new(), slot_count: 0, parameters, available: Vec::new() };
+    let result = compile(optimize(expression), &mut compilation)?;
+    Calculator::new(compilation, result, span)
+}
+
+/*function to check that each slot is stored before it is read, however each condition goes, and that every slot, parameter and jump is in
+range - slots take the place of a stack, so this takes the place of checking that the stack never underflows, and is checked for every
+expression so that calculating never reads outside the slots. Jumps only go forward, so the slots stored before each instruction are
+found in one pass, as those stored on every path to it*/
+fn slots_are_valid(instructions: &[CalculatorInstruction], result: Operand, slot_count: usize, parameter_count: usize) -> bool {
+    //slots stored on every path to each instruction, and to the end - None where there is no path
+    let mut stored: Vec<Option<Vec<bool>>> = vec![None; instructions.len() + 1];
+    stored[0] = Some(vec![false; slot_count]);
+    let readable = |operand: &Operand, stored: &[bool]| match *operand {
+        Operand::Slot(i) => i < slot_count && stored[i],
+        Operand::Parameter(i) => i < parameter_count,
+        _ => true
+    };
+    for pc in 0..instructions.len() {
+        let Some(mut current) = stored[pc].take() else { continue; };
+        let (reads, slot, next) = match &instructions[pc] {
+            CalculatorInstruction::Calculate(calculation, slot) => (calculation.operands(), Some(*slot), vec![pc + 1]),
+            CalculatorInstruction::Store(operand, slot) => (vec![operand], Some(*slot), vec![pc + 1]),
+            CalculatorInstruction::Jump(skip) => (Vec::new(), None, vec![pc + 1 + skip]),
+            CalculatorInstruction::JumpUnless(condition, skip) => (vec![condition], None, vec![pc + 1, pc + 1 + skip]),
+            CalculatorInstruction::Undefined => (Vec::new(), None, Vec::new())
+        };
+        if !reads.into_iter().all(|operand| readable(operand, &current)) { return false; }
+        if let Some(slot) = slot {
+            if slot >= slot_count { return false; }
+            current[slot] = true;
+        }
+        for next in next {
+            let Some(entry) = stored.get_mut(next) else { return false; };
+            *entry = Some(match entry.take() {
+                Some(other) => other.iter().zip(&current).map(|(a, b)| *a && *b).collect(),
+                None => current.clone()
+            });
+        }
+    }
+    stored[instructions.len()].as_ref().is_none_or(|stored| readable(&result, stored))
+}
+
+//function to add each parameter used in an expression that isn't in parameters yet to the end of it, in order of first use
+fn collect_parameters(expression: &ASTNode, parameters: &mut Vec<String>) {
+    match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::Parameter(name)) => {
+            if !parameters.contains(name) { parameters.push(name.clone()); }
+        },
+        _ => expression.children().into_iter().for_each(|child| collect_parameters(child, parameters))
     }
-    instructions
 }
 
-//function to create a new calculator and generate instructions for it given an AST and a delta
-pub fn generate_calculator(expression: ASTNodeType, delta: f64) -> Calculator {
-    let instructions = generate_instructions(expression);
-    Calculator::new(instructions, delta)
+//function to create a pair of calculators for the coordinates of each point in a list of points, given its AST
+pub fn generate_point_calculators(expression: ASTNode) -> Result<Vec<(Calculator, Calculator)>, EvalError> {
+    let ASTNodeType::ListExpression(points) = expression.node_type else { return Err(EvalError::UnexpectedPoint { span: expression.span }); };
+    points.into_iter().map(|point| match point.node_type {
+        ASTNodeType::PointExpression(x, y) => Ok((generate_calculator(*x)?, generate_calculator(*y)?)),
+        _ => Err(EvalError::UnexpectedPoint { span: point.span })
+    }).collect()
 }
+
  
 enum CalculatorInstruction {
-    Push(CalculatorValue),
+    //makes a calculation and stores its result in a slot
+    Calculate(Calculation, usize),
+    //stores the value of an operand in a slot - each branch of a condition stores its value in the same slot
+    Store(Operand, usize),
+    //skips the given number of instructions, unconditionally or if the operand is 0
+    Jump(usize),
+    JumpUnless(Operand, usize),
+    Undefined
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Calculation {
+    Unary(UnaryOperation, Operand),
+    Binary(BinaryOperation, Operand, Operand)
+}
+
+impl Calculation {
+    fn operands(&self) -> Vec<&Operand> {
+        match self {
+            Calculation::Unary(_, a) => vec![a],
+            Calculation::Binary(_, a, b) => vec![a, b]
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum UnaryOperation {
+    Neg,
+    //raises the operand to a whole number power
+    Powi(i32),
+    Factorial,
+    Function(Function)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum BinaryOperation {
     Add,
     Sub,
     Mul,
     Div,
     Exp,
-    ApplyFunc(TokenType)
+    Mod,
+    //1 if the comparison holds and 0 otherwise
+    Compare(TokenType),
+    Function(Function)
 }
 
-enum CalculatorValue {
-    Variable(TokenType),
-    Num(f64)
+//where an instruction reads a value from - a slot holds the result of an earlier instruction
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Operand {
+    X,
+    Y,
+    Parameter(usize),
+    Num(f64),
+    Slot(usize)
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{interpreter::generate_stack_calculator, scanner::{scan, RECOGNIZED_FUNCTIONS}, parser::parse};
+
+    fn calculate(expression: &str, x: f64) -> Option<f64> {
+        let ast = parse(&scan(expression).unwrap()).unwrap();
+        generate_calculator(ast).unwrap().calculate(x)
+    }
+
+    fn bound(expression: &str, start: f64, end: f64) -> Option<Bounds> {
+        let ast = parse(&scan(expression).unwrap()).unwrap();
+        generate_calculator(ast).unwrap().calculate_interval(start, end)
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn test_calculate() {
+        assert_close(calculate("2x^2 + 1", 3.0), 19.0);
+        assert_close(calculate("-x", 2.0), -2.0);
+        assert_eq!(calculate("1/x", 0.0), None);
+    }
+
+    #[test]
+    fn test_two_variables() {
+        let ast = parse(&scan("x^2 + 2y").unwrap()).unwrap();
+        let mut calculator = generate_calculator(ast).unwrap();
+        assert_close(calculator.calculate_point(3.0, 1.0), 11.0);
+        assert_close(calculate("y^2", 3.0), 9.0);
+    }
+
+    #[test]
+    fn test_parameters() {
+        let ast = parse(&scan("a*sin(b*x + c) + a").unwrap()).unwrap();
+        let mut calculator = generate_calculator(ast).unwrap();
+        assert_eq!(calculator.parameters(), ["a", "b", "c"]);
+        //parameters start at their default value
+        assert_close(calculator.calculate(0.0), 1.0_f64.sin() + 1.0);
+        assert!(calculator.set_parameter("a", 2.0));
+        assert!(calculator.set_parameter("b", 3.0));
+        assert!(calculator.set_parameter("c", 0.0));
+        assert!(!calculator.set_parameter("k", 5.0));
+        assert_close(calculator.calculate(PI / 6.0), 4.0);
+    }
+
+    #[test]
+    fn test_inverse_trig() {
+        assert_close(calculate("asin(x)", 1.0), PI / 2.0);
+        assert_close(calculate("acos(x)", -1.0), PI);
+        assert_close(calculate("atan(x)", 1.0), PI / 4.0);
+        assert_eq!(calculate("asin(x)", 1.5), None);
+        assert_eq!(calculate("acos(x)", -1.5), None);
+    }
+
+    #[test]
+    fn test_reciprocal_trig() {
+        assert_close(calculate("sec(x)", 0.0), 1.0);
+        assert_close(calculate("csc(x)", PI / 2.0), 1.0);
+        assert_close(calculate("cot(x)", PI / 4.0), 1.0);
+        assert_eq!(calculate("csc(x)", 0.0), None);
+        //points near a pole are still calculated, and the pole is found by bounding the function around it
+        assert!(calculate("sec(x)", PI / 2.0 - 1e-4).unwrap() > 1e3);
+        assert!(!bound("sec(x)", 1.57, 1.58).unwrap().continuous);
+        assert!(!bound("cot(x)", PI - 0.01, PI + 0.01).unwrap().continuous);
+    }
+
+    #[test]
+    fn test_hyperbolic() {
+        assert_close(calculate("cosh(x)^2 - sinh(x)^2", 1.7), 1.0);
+        assert_close(calculate("tanh(x)", 0.0), 0.0);
+        assert_close(calculate("asinh(sinh(x))", 2.0), 2.0);
+        assert_close(calculate("acosh(x)", 1.0), 0.0);
+        assert_close(calculate("atanh(tanh(x))", 0.5), 0.5);
+        assert_eq!(calculate("acosh(x)", 0.5), None);
+        assert_eq!(calculate("atanh(x)", 1.0), None);
+        assert_eq!(calculate("sinh(x)", 1000.0), None);
+    }
+
+    #[test]
+    fn test_other_functions() {
+        assert_close(calculate("exp(x)", 1.0), E);
+        assert_eq!(calculate("exp(x)", 1000.0), None);
+        assert_close(calculate("floor(x)", -1.5), -2.0);
+        assert_close(calculate("ceil(x)", -1.5), -1.0);
+        assert_close(calculate("round(x)", 2.5), 3.0);
+        assert_close(calculate("sign(x)", -3.0), -1.0);
+        assert_close(calculate("sign(x)", 0.0), 0.0);
+        assert_close(calculate("cbrt(x)", -8.0), -2.0);
+    }
+
+    #[test]
+    fn test_multiple_arguments() {
+        assert_close(calculate("log(2, x)", 8.0), 3.0);
+        assert_eq!(calculate("log(1, x)", 8.0), None);
+        assert_close(calculate("max(x, 2, -x)", -3.0), 3.0);
+        assert_close(calculate("min(x, 2)", 5.0), 2.0);
+        assert_close(calculate("atan2(x, -1)", 0.0), PI);
+        assert_close(calculate("root(3, x)", -27.0), -3.0);
+        assert_close(calculate("root(2, x)", 16.0), 4.0);
+        assert_eq!(calculate("root(2, x)", -16.0), None);
+        assert_close(calculate("mod(x, 3)", -1.0), 2.0);
+        assert_close(calculate("mod(x, -3)", 1.0), -2.0);
+        assert_eq!(calculate("mod(x, 0)", 1.0), None);
+        assert_close(calculate("mod(x, 0.0001)", 0.00025), 0.00005);
+        //arguments are passed in order, not reversed by the stack
+        assert_close(calculate("mod(7, x) + log(x, 9)", 3.0), 3.0);
+    }
+
+    #[test]
+    fn test_factorial() {
+        assert_close(calculate("x!", 0.0), 1.0);
+        assert_close(calculate("x!", 5.0), 120.0);
+        assert_close(calculate("x!", 0.5), PI.sqrt() / 2.0);
+        assert_close(calculate("x!", -0.5), PI.sqrt());
+        assert_close(calculate("x!!", 3.0), 720.0);
+        assert_eq!(calculate("x!", -1.0), None);
+        assert_eq!(calculate("x!", -3.0), None);
+        assert_eq!(calculate("x!", 200.0), None);
+        //gamma interpolates smoothly between the integer factorials
+        let (below, above) = (calculate("x!", 3.0 - 1e-7).unwrap(), calculate("x!", 3.0 + 1e-7).unwrap());
+        assert!((below - 6.0).abs() < 1e-5 && (above - 6.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_mod_operator() {
+        assert_close(calculate("x mod 3", 7.0), 1.0);
+        assert_close(calculate("x % 3", -1.0), 2.0);
+        assert_close(calculate("x mod -3", 1.0), -2.0);
+        assert_close(calculate("x mod 2.5", 6.0), 1.0);
+        assert_eq!(calculate("3 mod x", 0.0), None);
+    }
+
+    #[test]
+    fn test_piecewise() {
+        assert_close(calculate("{x < 0: -x, x >= 0: sqrt(x)}", -4.0), 4.0);
+        assert_close(calculate("{x < 0: -x, x >= 0: sqrt(x)}", 4.0), 2.0);
+        assert_close(calculate("x^2 {x < 0}", -3.0), 9.0);
+        assert_eq!(calculate("x^2 {x < 0}", 3.0), None);
+        assert_close(calculate("{x < 0: 1, 2} + 1", 5.0), 3.0);
+        //branches that aren't taken are never calculated, so can be undefined
+        assert_close(calculate("if(x > 1, 1/x, x)", 0.0), 0.0);
+        assert_close(calculate("if(x > 1, 1/x, x)", 2.0), 0.5);
+        assert_eq!(calculate("if(x > 1, 1/x)", 1.0), None);
+        //the first branch whose condition holds is used
+        assert_close(calculate("{x > 0: 1, x > 1: 2}", 3.0), 1.0);
+    }
+
+    #[test]
+    fn test_conditions() {
+        assert_close(calculate("{0 < x <= 1 or x > 3: 1, 0}", 1.0), 1.0);
+        assert_close(calculate("{0 < x <= 1 or x > 3: 1, 0}", 2.0), 0.0);
+        assert_close(calculate("{0 < x <= 1 or x > 3: 1, 0}", 4.0), 1.0);
+        assert_close(calculate("{0 < x <= 1 or x > 3: 1, 0}", 0.0), 0.0);
+        assert_close(calculate("{x > -1 and x < 1: 1, 0}", 0.5), 1.0);
+        assert_close(calculate("{x > -1 and x < 1: 1, 0}", 1.5), 0.0);
+        assert_close(calculate("{x >= 2 ∧ x <= 3 ∨ x < -5: 1, 0}", -6.0), 1.0);
+        assert_close(calculate("{x >= 2 ∧ x <= 3 ∨ x < -5: 1, 0}", 3.5), 0.0);
+    }
+
+    #[test]
+    fn test_calculate_interval() {
+        let bounds = bound("x^2 - 2x", 0.0, 3.0).unwrap();
+        assert!(bounds.lo <= -1.0 && bounds.hi >= 3.0 && bounds.continuous);
+        //small divisors that aren't 0 are still defined
+        assert!(bound("1/(0.001x)", 0.5, 0.6).unwrap().continuous);
+        assert!(!bound("1/x", -0.01, 0.01).unwrap().continuous);
+        assert!(!bound("tan(x)", 1.57, 1.58).unwrap().continuous);
+        assert!(!bound("floor(x)", 0.99, 1.01).unwrap().continuous);
+        assert_eq!(bound("sqrt(x)", -2.0, -1.0), None);
+        //a condition that goes either way bounds both branches, and jumps
+        let bounds = bound("{x < 0: -1, 1}", -1.0, 1.0).unwrap();
+        assert!((bounds.lo, bounds.hi) == (-1.0, 1.0) && !bounds.continuous);
+        assert_eq!(bound("{x < 0: -1, 1}", 1.0, 2.0), Some(Bounds::point(1.0)));
+        assert_eq!(bound("x^2 {x < 0}", 1.0, 2.0), None);
+        assert!(!bound("x^2 {x < 0}", -1.0, 1.0).unwrap().defined);
+    }
+
+    #[test]
+    fn test_interval_conditions_merge() {
+        //each undecided condition would double the paths followed if they weren't merged where their branches meet
+        let terms: Vec<String> = (1..=40).map(|i| format!("{{x < {}: 1, 2}}", i as f64 / 41.0)).collect();
+        let mut calculator = generate_calculator(parse(&scan(&terms.join(" + ")).unwrap()).unwrap()).unwrap();
+        let bounds = calculator.calculate_interval(0.0, 1.0).unwrap();
+        assert!((bounds.lo, bounds.hi) == (40.0, 80.0) && bounds.defined && !bounds.continuous);
+        //so there are never more than two copies of the slots at once, whose buffers are kept for the next call
+        assert!(calculator.scratch.spare.len() <= 2);
+        let bounds = bound(&format!("{} + {{x < 0.5: 1}}", terms.join(" + ")), 0.0, 1.0).unwrap();
+        assert!((bounds.lo, bounds.hi) == (41.0, 81.0) && !bounds.defined);
+    }
+
+    #[test]
+    fn test_calculate_derivative() {
+        let derivative = |expression: &str, x: f64| {
+            let ast = parse(&scan(expression).unwrap()).unwrap();
+            generate_calculator(ast).unwrap().calculate_derivative(x)
+        };
+        assert_eq!(derivative("3x^2 - 2x + 1", 2.0), Some((9.0, 10.0)));
+        assert_eq!(derivative("x/(x + 1)", 1.0), Some((0.5, 0.25)));
+        assert_eq!(derivative("x mod 3", 4.0), Some((1.0, 1.0)));
+        assert_eq!(derivative("-x", 4.0), Some((-4.0, -1.0)));
+        let (value, slope) = derivative("sin(x)^2 + e^(2x)", 0.5).unwrap();
+        assert!((value - (0.5_f64.sin().powi(2) + 1.0_f64.exp())).abs() < 1e-12);
+        assert!((slope - (1.0_f64.sin() + 2.0 * 1.0_f64.exp())).abs() < 1e-12);
+        let (value, slope) = derivative("x!", 3.0).unwrap();
+        assert!(value == 6.0 && (slope - 6.0 * (11.0 / 6.0 - 0.577_215_664_901_532_9)).abs() < 1e-9);
+        //the derivative follows the branch that is taken, and conditions are flat
+        assert_eq!(derivative("{x < 0: -x^2, x^3}", -1.0), Some((-1.0, 2.0)));
+        assert_eq!(derivative("{x < 0: -x^2, x^3}", 2.0), Some((8.0, 12.0)));
+        assert_eq!(derivative("{x > 0 and x < 1: 2x, 0}", 0.5), Some((1.0, 2.0)));
+        assert_eq!(derivative("x^2 {x < 0}", 1.0), None);
+        assert_eq!(derivative("sqrt(x)", 0.0), None);
+        assert_eq!(derivative("abs(x)", 0.0), Some((0.0, 0.0)));
+        //parameters are constants
+        assert_eq!(derivative("a x^2", 3.0), Some((9.0, 6.0)));
+    }
+
+    #[test]
+    fn test_optimized_instructions() {
+        let generate = |expression: &str| generate_calculator(parse(&scan(expression).unwrap()).unwrap()).unwrap();
+        assert_eq!(generate("2 * pi / 4").instructions.len(), 0);
+        assert_eq!(generate("-x").instructions.len(), 1);
+        assert_eq!(generate("x * 1 + 0 + sin(0)").instructions.len(), 0);
+        //parameters keep the order they are written in, even where optimizing reorders them
+        assert_eq!(generate("b x + a").parameters(), ["b", "a"]);
+        //whole number powers are calculated by multiplication, and other powers as before
+        assert!(matches!(generate("x^3").instructions[..], [CalculatorInstruction::Calculate(Calculation::Unary(UnaryOperation::Powi(3), _), _)]));
+        assert!(matches!(generate("x^0.5").instructions[..], [CalculatorInstruction::Calculate(Calculation::Binary(BinaryOperation::Exp, _, _), _)]));
+        //optimized calculators are defined in the same places as the expressions they were generated from
+        for expression in ["x^2 - 2x + x - 1", "(x + 4)^-2 + (x - 1)^3","-(-x) / -3", "0 * ln(x) + 2^3", "(x + 1) mod (5 - 2)", "{x < 0: -x, 1 > 2: x, sqrt(x)}", "a x - a x"] {
+            let ast = parse(&scan(expression).unwrap()).unwrap();
+            let mut unoptimized = generate_stack_calculator(ast.clone()).unwrap();
+            let mut optimized = generate_calculator(ast).unwrap();
+            for x in [-2.5, -1.0, 0.0, 0.5, 3.0] {
+                match (unoptimized.calculate(x), optimized.calculate(x)) {
+                    (Some(expected), actual) => assert_close(actual, expected),
+                    (None, actual) => assert_eq!(actual, None, "{} at {}", expression, x)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_calculations() {
+        let generate = |expression: &str| generate_calculator(parse(&scan(expression).unwrap()).unwrap()).unwrap();
+        let count = |calculator: &Calculator, function: Function| calculator.instructions.iter().filter(|instruction| {
+            matches!(instruction, CalculatorInstruction::Calculate(Calculation::Unary(UnaryOperation::Function(f), _), _) if *f == function)
+        }).count();
+        let mut calculator = generate("sin(x)^2 + sin(x) cos(x)");
+        assert_eq!(count(&calculator, Function::Sin), 1);
+        assert_close(calculator.calculate(0.7), 0.7_f64.sin().powi(2) + 0.7_f64.sin() * 0.7_f64.cos());
+        //a calculation made in one branch isn't reused outside it, where the branch may not have been taken
+        let mut calculator = generate("{x > 0: sqrt(x), 1} / sqrt(x)");
+        assert_eq!(count(&calculator, Function::Sqrt), 2);
+        assert_close(calculator.calculate(4.0), 1.0);
+        assert_eq!(calculator.calculate(-4.0), None);
+        //but one made before a branch is
+        let mut calculator = generate("sqrt(x) + {x > 0: sqrt(x), 1}");
+        assert_eq!(count(&calculator, Function::Sqrt), 1);
+        assert!(calculator.calculate_interval(1.0, 4.0).unwrap().contains(3.0));
+    }
+
+    #[test]
+    fn test_invalid_instructions() {
+        let span = Span::new(0, 1);
+        let check = |instructions: Vec<CalculatorInstruction>, result: Operand| {
+            let compilation = Compilation { instructions, slot_count: 2, parameters: vec![String::from("a")], available: Vec::new() };
+            Calculator::new(compilation, result, span).err()
+        };
+        let add = |a: Operand, b: Operand, slot: usize| CalculatorInstruction::Calculate(Calculation::Binary(BinaryOperation::Add, a, b), slot);
+        assert_eq!(check(vec![add(Operand::X, Operand::Parameter(0), 0), add(Operand::Slot(0), Operand::X, 1)], Operand::Slot(1)), None);
+        let invalid = Some(EvalError::InvalidInstructions { span });
+        //a slot read before it is stored
+        assert_eq!(check(vec![add(Operand::Slot(1), Operand::X, 0)], Operand::Slot(0)), invalid);
+        assert_eq!(check(vec![add(Operand::X, Operand::X, 0)], Operand::Slot(1)), invalid);
+        //a slot only stored when a condition holds
+        let branch = vec![CalculatorInstruction::JumpUnless(Operand::X, 1), CalculatorInstruction::Store(Operand::X, 0)];
+        assert_eq!(check(branch, Operand::Slot(0)), invalid);
+        //slots, parameters and jumps out of range
+        assert_eq!(check(vec![add(Operand::X, Operand::X, 2)], Operand::Slot(2)), invalid);
+        assert_eq!(check(vec![add(Operand::X, Operand::Parameter(1), 0)], Operand::Slot(0)), invalid);
+        assert_eq!(check(vec![CalculatorInstruction::Jump(2)], Operand::X), invalid);
+    }
+
+    #[test]
+    fn test_compiled_matches_interpreter() {
+        let mut expressions = vec![String::from("{0 < x <= 1 or x > 3: x^2, x >= -1 and x < 0: -x, 5 mod x} + x!")];
+        for name in RECOGNIZED_FUNCTIONS {
+            assert_eq!(Function::from_name(name).unwrap().name(), *name);
+            let arguments = match *name {
+                "max" | "min" => "x, 2x - 1, sin(x), x/2",
+                "atan2" | "root" | "mod" | "log" => "x + 2, x^2 - 1",
+                _ => "x - 0.5"
+            };
+            expressions.push(format!("{}({}) + {}({}) / 2", name, arguments, name, arguments));
+        }
+        for expression in &expressions {
+            let ast = parse(&scan(expression).unwrap()).unwrap();
+            let mut interpreted = generate_stack_calculator(ast.clone()).unwrap();
+            let mut compiled = generate_calculator(ast).unwrap();
+            for i in -40..40 {
+                let x = i as f64 * 0.1 + 0.05;
+                match (interpreted.calculate(x), compiled.calculate(x)) {
+                    (Some(expected), actual) => assert!((actual.unwrap() - expected).abs() <= 1e-9 * expected.abs().max(1.0), "{} at {}", expression, x),
+                    (None, actual) => assert_eq!(actual, None, "{} at {}", expression, x)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_log_domain() {
+        assert_close(calculate("log(x)", 100.0), 2.0);
+        assert_eq!(calculate("log(x)", 0.0), None);
+        assert_eq!(calculate("ln(x)", -1.0), None);
+    }
+}
@@ -0,0 +1,571 @@
+/*
+NAMED FUNCTIONS:
+Each input can be called from the others by its position, as f1( ) to f5( ), and an input of the form g(x) = x^2 + 1 can also be
+called by its name, as g( ). A definition's parameter can be x, y or θ, as in h(y) = 2y, and its body can't use the other variables. A
+call is expanded inline, replacing the variable of the called expression with the argument, so f1(x) + g(2x) produces a single AST, and
+any parameters used by the called expression become parameters of the expression that calls it.
+
+The calls between inputs form a dependency graph. An input that depends on itself, directly or through other inputs, is an error, as is
+an input that calls a function which is not defined or which has an error itself. Since every input is resolved again each time
+initialize is called, changing a definition re-evaluates every input that depends on it.
+
+EQUATIONS:
+An equation y = f(x) (or f(x) = y) is graphed as f(x), and an equation x = g(y) (or g(y) = x) is graphed as g(y), sampled along the 
+y-axis, so x = 3 is a vertical line. Any other equation, such as x^2 + y^2 = 25, is graphed as an implicit curve, the curve where
+the difference of its two sides is 0. Calling an equation's input calls the function it is graphed as, so an implicit curve can't be
+called. y can only be used in equations and inequalities.
+
+INEQUALITIES:
+An inequality is graphed as the region where it holds. As with equations, y < f(x) (or f(x) > y) is the region on one side of f(x), 
+x > g(y) is the region on one side of g(y), and any other inequality, such as x^2 + y^2 < 4, is the region where the difference of its
+two sides is on one side of 0. Strict inequalities don't include their boundary, so it is drawn dashed. Inequalities can't be called.
+
+PARAMETRIC CURVES:
+A parametric curve is given by two expressions of t, x(t) and y(t), and is graphed over a range of t set along with the curve (see 
+initialize_parametric in lib.rs). t is only a variable in the coordinates of a parametric curve, and is a parameter in any other input.
+Coordinates can use parameters, but not x or y, and can't call other inputs.
+
+POINTS:
+A list of points, such as (1, 2), (3, 4), is graphed as a marker at each point. Coordinates of points can use parameters and call other
+inputs, but can't use x or y, and a list of points can't be called.
+
+POLAR FUNCTIONS:
+In polar mode, a function is graphed as r = f(θ), where θ can be written as theta, θ or x, for θ from 0 to 2π unless another range is 
+set for it (see set_theta_range in lib.rs). A function that only uses θ in the arguments of trig functions, where each argument is a 
+rational multiple of θ plus a constant, as in sin(5θ/3), repeats after a whole number of turns, and its default range is extended to
+that many turns, so that roses are drawn completely. θ can't be used along with x or y, or in an equation, inequality or coordinate, so a
+polar function is written as f(θ) on its own rather than as r = f(θ).
+*/
+
+use std::f64::consts::{E, PI};
+
+use crate::{error::{EvalError, ExpressionError}, graph::{Axis, Inequality, PlotKind, Side}, 
+            parser::{definition_name, parse, parse_statement, ASTNode, ASTNodeType, Statement}, scanner::{mark_user_functions, mark_variable, scan, Span, TokenType, RECOGNIZED_VARIABLES}};
+
+//the variable the coordinates of a parametric curve are expressions of
+const PARAMETRIC_VARIABLE: &str = "t";
+//functions whose period divides 2π, so that they repeat after a whole number of turns of a rational multiple of θ
+const TRIG_FUNCTIONS: &[&str] = &["sin", "cos", "tan", "sec", "csc", "cot"];
+//largest denominator of a rational multiple of θ that is detected
+const MAX_ANGLE_DENOMINATOR: u64 = 32;
+//most turns a polar function's default range of θ is extended to
+const MAX_THETA_TURNS: u64 = 64;
+
+type Parsed = Option<Result<Statement, ExpressionError>>;
+type Expanded = Option<Result<(ASTNode, PlotKind), ExpressionError>>;
+
+//an input after the calls in it have been expanded
+#[derive(Debug)]
+pub struct ResolvedInput {
+    //the expanded expression, plotted as kind
+    pub expression: ASTNode,
+    pub kind: PlotKind,
+    //the input as it was parsed, fully parenthesized and with calls left unexpanded
+    pub parsed: String
+}
+
+//function to scan and parse each input and expand the calls between them - produces None for empty inputs
+pub fn resolve_definitions(expressions: &[String]) -> Vec<Option<Result<ResolvedInput, ExpressionError>>> {
+    //scan every input before parsing any of them, so that the names defined by every input are known
+    let scanned: Vec<_> = expressions.iter().map(|expression| {
+        if expression.trim().is_empty() { None } else { Some(scan(expression)) }
+    }).collect();
+    let defined: Vec<String> = scanned.iter().filter_map(|tokens| match tokens {
+        Some(Ok(tokens)) => definition_name(tokens),
+        _ => None
+    }).collect();
+
+    let mut names = Vec::new();
+    let mut parsed = Vec::new();
+    for (i, tokens) in scanned.into_iter().enumerate() {
+        let Some(tokens) = tokens else {
+            names.push(None);
+            parsed.push(None);
+            continue;
+        };
+        //an input with an error still defines its name, so that calls to it report the error rather than an undefined function
+        let name = tokens.as_ref().ok().and_then(|tokens| definition_name(tokens));
+        let definition = tokens.map_err(ExpressionError::from).and_then(|mut tokens| {
+            mark_user_functions(&mut tokens, &defined);
+            Ok(parse_statement(&tokens)?)
+        });
+        let definition = match definition {
+            //each name can only be defined once, and fN can only name the Nth input
+            Ok(Statement::Definition(name, span, _, _)) if names.contains(&Some(name.clone())) || input_number(&name).is_some_and(|n| n != i + 1) => {
+                Err(EvalError::DuplicateDefinition { name, span }.into())
+            }
+            definition => definition
+        };
+        names.push(name);
+        parsed.push(Some(definition));
+    }
+
+    let mut expanded = vec![None; parsed.len()];
+    (0..parsed.len()).map(|i| {
+        let definition = parsed[i].as_ref()?;
+        let result = expand_definition(i, &parsed, &names, &mut expanded).map(|(expression, kind)| {
+            //echo back the input as it was parsed, rather than as it was expanded
+            ResolvedInput { expression, kind, parsed: definition.as_ref().unwrap().to_string() }
+        });
+        Some(result)
+    }).collect()
+}
+
+//function to scan and parse one coordinate of a parametric curve, x(t) or y(t)
+pub fn resolve_parametric(expression: &str) -> Result<ASTNode, ExpressionError> {
+    let mut tokens = scan(expression)?;
+    mark_variable(&mut tokens, PARAMETRIC_VARIABLE);
+    let expression = parse(&tokens)?;
+    uses_only(&expression, &[])?;
+    Ok(expression)
+}
+
+//function to get the range of θ a polar function is graphed over by default - one whole period if it repeats after a whole number of turns
+pub fn default_theta_range(expression: &ASTNode) -> (f64, f64) {
+    let turns = angle_turns(expression).filter(|turns| *turns <= MAX_THETA_TURNS).unwrap_or(1);
+    (0.0, 2.0 * PI * turns as f64)
+}
+
+//function to get the number of turns of θ after which an expression repeats, if it does - an expression that doesn't use θ repeats after 1
+fn angle_turns(expression: &ASTNode) -> Option<u64> {
+    match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::Variable(_)) => None,
+        ASTNodeType::FunctionExpression(TokenType::FunctionName(name), arguments) if TRIG_FUNCTIONS.contains(&name.as_str()) => {
+            //sin(kθ + c) for k = p/q in lowest terms repeats after q turns
+            angle_multiple(&arguments[0]).and_then(denominator).or_else(|| angle_turns(&arguments[0]))
+        }
+        _ => expression.children().into_iter().try_fold(1, |turns, child| Some(lcm(turns, angle_turns(child)?)))
+    }
+}
+
+//function to get k if an expression is kθ + c, for constants k and c
+fn angle_multiple(expression: &ASTNode) -> Option<f64> {
+    if constant_value(expression).is_some() { return Some(0.0); }
+    match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::Variable(_)) => Some(1.0),
+        ASTNodeType::BinaryExpression(TokenType::Add, left, right) => Some(angle_multiple(left)? + angle_multiple(right)?),
+        ASTNodeType::BinaryExpression(TokenType::Sub, left, right) => Some(angle_multiple(left)? - angle_multiple(right)?),
+        ASTNodeType::BinaryExpression(TokenType::Mul, left, right) => match (constant_value(left), constant_value(right)) {
+            (Some(c), _) => Some(c * angle_multiple(right)?),
+            (_, Some(c)) => Some(angle_multiple(left)? * c),
+            _ => None
+        },
+        ASTNodeType::BinaryExpression(TokenType::Div, left, right) => Some(angle_multiple(left)? / constant_value(right)?),
+        ASTNodeType::UnaryExpression(TokenType::Sub, operand) => Some(-angle_multiple(operand)?),
+        _ => None
+    }
+}
+
+//function to get the value of an expression of numbers and constants alone
+fn constant_value(expression: &ASTNode) -> Option<f64> {
+    match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::NumLiteral(num)) => Some(*num),
+        ASTNodeType::AtomicExpression(TokenType::Constant(c)) if c == "pi" => Some(PI),
+        ASTNodeType::AtomicExpression(TokenType::Constant(c)) if c == "e" => Some(E),
+        ASTNodeType::UnaryExpression(TokenType::Sub, operand) => Some(-constant_value(operand)?),
+        ASTNodeType::BinaryExpression(operator, left, right) => {
+            let (left, right) = (constant_value(left)?, constant_value(right)?);
+            match operator {
+                TokenType::Add => Some(left + right),
+                TokenType::Sub => Some(left - right),
+                TokenType::Mul => Some(left * right),
+                TokenType::Div => Some(left / right),
+                _ => None
+            }
+        }
+        _ => None
+    }
+}
+
+//function to get q if k is p/q in lowest terms, for q up to MAX_ANGLE_DENOMINATOR
+fn denominator(k: f64) -> Option<u64> {
+    (1..=MAX_ANGLE_DENOMINATOR).find(|q| (k * *q as f64 - (k * *q as f64).round()).abs() < 1e-9)
+}
+
+//function to get the least common multiple of two positive integers
+fn lcm(a: u64, b: u64) -> u64 {
+    let gcd = |mut a: u64, mut b: u64| {
+        while b != 0 { (a, b) = (b, a % b); }
+        a
+    };
+    a / gcd(a, b) * b
+}
+
+//function to get N if name is fN, the name that refers to the Nth input
+fn input_number(name: &str) -> Option<usize> {
+    name.strip_prefix('f').and_then(|digits| digits.parse().ok())
+}
+
+//function to find the index of the input a name refers to, if there is one
+fn find_definition(name: &str, parsed: &[Parsed], names: &[Option<String>]) -> Option<usize> {
+    let i = match input_number(name) {
+        Some(n) => n.checked_sub(1)?,
+        None => names.iter().position(|defined| defined.as_deref() == Some(name))?
+    };
+    parsed.get(i)?.as_ref().map(|_| i)
+}
+
+/*function to produce the expanded AST of input i, along with how it is plotted, expanding the inputs it calls first - results 
+are stored in expanded, so each input is only expanded once*/
+fn expand_definition(i: usize, parsed: &[Parsed], names: &[Option<String>],
+                     expanded: &mut [Expanded]) -> Result<(ASTNode, PlotKind), ExpressionError> {
+    if let Some(result) = &expanded[i] { return result.clone(); }
+    let result = match parsed[i].as_ref().unwrap() {
+        Ok(Statement::Definition(_, _, parameter, expression)) => {
+            //a definition can only use its parameter, which is renamed to x so that it is sampled along the x-axis like any other function
+            expand_calls(i, expression, parsed, names, expanded).and_then(|expression| {
+                uses_only(&expression, &[parameter])?;
+                Ok((rename_variable(&expression, parameter, "x"), PlotKind::Function(Axis::X)))
+            })
+        }
+        Ok(Statement::Expression(expression)) => {
+            expand_calls(i, expression, parsed, names, expanded).and_then(|expression| match expression.node_type {
+                ASTNodeType::InequalityExpression(operator, left, right) => {
+                    uses_only(&left, &["x", "y"])?;
+                    uses_only(&right, &["x", "y"])?;
+                    Ok(classify_inequality(&operator, *left, *right))
+                }
+                ASTNodeType::ListExpression(_) => {
+                    uses_only(&expression, &[])?;
+                    Ok((expression, PlotKind::Points))
+                }
+                _ => {
+                    let axis = function_of(Axis::X, &expression)?;
+                    Ok((expression, PlotKind::Function(axis)))
+                }
+            })
+        }
+        Ok(Statement::Equation(left, right)) => {
+            expand_calls(i, left, parsed, names, expanded).and_then(|left| {
+                let right = expand_calls(i, right, parsed, names, expanded)?;
+                uses_only(&left, &["x", "y"])?;
+                uses_only(&right, &["x", "y"])?;
+                Ok(classify_equation(left, right))
+            })
+        }
+        Err(e) => Err(e.clone())
+    };
+    expanded[i] = Some(result.clone());
+    result
+}
+
+/*function to replace each call to another input in an AST of input i with the expanded AST of that input - cycles are checked for before
+expanding a call, so expanding never recurses back into an input that is still being expanded*/
+fn expand_calls(i: usize, expression: &ASTNode, parsed: &[Parsed], names: &[Option<String>],
+                expanded: &mut [Expanded]) -> Result<ASTNode, ExpressionError> {
+    let span = expression.span;
+    let node_type = match &expression.node_type {
+        ASTNodeType::FunctionExpression(TokenType::UserFunction(name), arguments) => {
+            let name = name.clone();
+            let Some(called) = find_definition(&name, parsed, names) else { return Err(EvalError::UndefinedFunction { name, span }.into()); };
+            if depends_on(called, i, parsed, names, &mut vec![false; parsed.len()]) {
+                return Err(EvalError::CircularDefinition { name, span }.into());
+            }
+            let Ok((body, kind)) = expand_definition(called, parsed, names, expanded) else { return Err(EvalError::InvalidReference { name, span }.into()); };
+            let PlotKind::Function(axis) = kind else { return Err(EvalError::NotAFunction { name, span }.into()); };
+            let argument = expand_calls(i, &arguments[0], parsed, names, expanded)?;
+            return Ok(substitute(&body, sampled_variables(axis), &argument, span));
+        }
+        _ => expression.try_map_children(|child| expand_calls(i, child, parsed, names, expanded))?
+    };
+    Ok(ASTNode::new(node_type, span))
+}
+
+/*function to get the expression an equation is graphed as, and how it is plotted - if one side is y or x on its own, and the other side
+doesn't use that variable, the other side is a function of the other variable, and otherwise the equation is an implicit curve*/
+fn classify_equation(left: ASTNode, right: ASTNode) -> (ASTNode, PlotKind) {
+    match solve_for_variable(&left, &right) {
+        Some((axis, expression, _)) => (expression.clone(), PlotKind::Function(axis)),
+        None => (difference(left, right), PlotKind::Implicit)
+    }
+}
+
+//function to get the expression an inequality's region is bounded by, and how it is plotted - as for classify_equation
+fn classify_inequality(operator: &TokenType, left: ASTNode, right: ASTNode) -> (ASTNode, PlotKind) {
+    let side = if matches!(operator, TokenType::Less | TokenType::LessEqual) { Side::Less } else { Side::Greater };
+    let inclusive = matches!(operator, TokenType::LessEqual | TokenType::GreaterEqual);
+    match solve_for_variable(&left, &right) {
+        Some((axis, expression, variable_on_left)) => {
+            //f(x) > y is y < f(x), so flip the side when the variable is on the right
+            let side = match (variable_on_left, side) {
+                (true, side) => side,
+                (false, Side::Less) => Side::Greater,
+                (false, Side::Greater) => Side::Less
+            };
+            (expression.clone(), PlotKind::FunctionRegion(axis, Inequality { side, inclusive }))
+        }
+        None => (difference(left, right), PlotKind::ImplicitRegion(Inequality { side, inclusive }))
+    }
+}
+
+/*function to check whether one side of an equation or inequality is y or x on its own, and the other side doesn't use that variable - 
+produces the axis the other side is sampled along (the x-axis for y = f(x), and the y-axis for x = g(y)), the other side, and whether the
+variable was on the left*/
+fn solve_for_variable<'a>(left: &'a ASTNode, right: &'a ASTNode) -> Option<(Axis, &'a ASTNode, bool)> {
+    let (variable, expression, variable_on_left) = match (&left.node_type, &right.node_type) {
+        (ASTNodeType::AtomicExpression(TokenType::Variable(variable)), _) if find_variable(right, variable).is_none() => (variable, right, true),
+        (_, ASTNodeType::AtomicExpression(TokenType::Variable(variable))) if find_variable(left, variable).is_none() => (variable, left, false),
+        _ => return None
+    };
+    let axis = match variable.as_str() {
+        "y" => Axis::X,
+        "x" => Axis::Y,
+        _ => return None
+    };
+    Some((axis, expression, variable_on_left))
+}
+
+//function to produce the AST of left - right
+fn difference(left: ASTNode, right: ASTNode) -> ASTNode {
+    let span = left.span.to(right.span);
+    ASTNode::new(ASTNodeType::BinaryExpression(TokenType::Sub, Box::new(left), Box::new(right)), span)
+}
+
+/*function to check that an expression sampled along axis only uses the variable of that axis - produces the axis if it does. θ is sampled
+like x, but can't be used along with it*/
+fn function_of(axis: Axis, expression: &ASTNode) -> Result<Axis, EvalError> {
+    let variable = match axis {
+        Axis::X if find_variable(expression, "x").is_none() => "theta",
+        Axis::X => "x",
+        Axis::Y => "y"
+    };
+    uses_only(expression, &[variable])?;
+    Ok(axis)
+}
+
+/*function to check that an expression uses no variable other than those allowed - coordinates that are graphed as they are allow none, and
+equations and inequalities allow x and y*/
+fn uses_only(expression: &ASTNode, allowed: &[&str]) -> Result<(), EvalError> {
+    for variable in RECOGNIZED_VARIABLES.iter().filter(|variable| !allowed.contains(variable)) {
+        if let Some(span) = find_variable(expression, variable) {
+            return Err(EvalError::UnexpectedVariable { name: String::from(*variable), span });
+        }
+    }
+    Ok(())
+}
+
+//function to get the variables a function sampled along axis is an expression of - θ is sampled like x
+fn sampled_variables(axis: Axis) -> &'static [&'static str] {
+    if axis == Axis::X { &["x", "theta"] } else { &["y"] }
+}
+
+//function to find the span of the first use of a variable in an AST, if it is used
+fn find_variable(expression: &ASTNode, name: &str) -> Option<Span> {
+    match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::Variable(variable)) if variable == name => Some(expression.span),
+        _ => expression.children().into_iter().find_map(|child| find_variable(child, name))
+    }
+}
+
+//function to check whether input i calls input target, directly or through other inputs - visited marks the inputs already searched
+fn depends_on(i: usize, target: usize, parsed: &[Parsed], names: &[Option<String>], visited: &mut [bool]) -> bool {
+    if i == target { return true; }
+    if visited[i] { return false; }
+    visited[i] = true;
+    let mut calls = Vec::new();
+    match &parsed[i] {
+        Some(Ok(Statement::Expression(expression))) | Some(Ok(Statement::Definition(_, _, _, expression))) => collect_calls(expression, &mut calls),
+        Some(Ok(Statement::Equation(left, right))) => {
+            collect_calls(left, &mut calls);
+            collect_calls(right, &mut calls);
+        }
+        _ => return false
+    }
+    calls.into_iter().filter_map(|name| find_definition(name, parsed, names)).any(|called| depends_on(called, target, parsed, names, visited))
+}
+
+//function to add the name of each user defined function called in an AST to calls
+fn collect_calls<'a>(expression: &'a ASTNode, calls: &mut Vec<&'a str>) {
+    if let ASTNodeType::FunctionExpression(TokenType::UserFunction(name), _) = &expression.node_type { calls.push(name); }
+    expression.children().into_iter().for_each(|child| collect_calls(child, calls));
+}
+
+/*function to produce a copy of the expanded AST of a called input with its variables replaced by the argument of the call - the copied nodes
+take the span of the call, since their own spans refer to the called input*/
+fn substitute(body: &ASTNode, variables: &[&str], argument: &ASTNode, span: Span) -> ASTNode {
+    let node_type = match &body.node_type {
+        ASTNodeType::AtomicExpression(TokenType::Variable(variable)) if variables.contains(&variable.as_str()) => return argument.clone(),
+        _ => body.map_children(|child| substitute(child, variables, argument, span))
+    };
+    ASTNode::new(node_type, span)
+}
+
+//function to produce a copy of an AST with each use of one variable renamed to another
+fn rename_variable(expression: &ASTNode, from: &str, to: &str) -> ASTNode {
+    let node_type = match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::Variable(variable)) if variable == from => ASTNodeType::AtomicExpression(TokenType::Variable(String::from(to))),
+        _ => expression.map_children(|child| rename_variable(child, from, to))
+    };
+    ASTNode::new(node_type, expression.span)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculator::generate_calculator;
+
+    fn resolve(inputs: &[&str]) -> Vec<Option<Result<ResolvedInput, ExpressionError>>> {
+        let inputs: Vec<String> = inputs.iter().map(|input| input.to_string()).collect();
+        resolve_definitions(&inputs)
+    }
+
+    //function to evaluate input i of the given inputs at x
+    fn calculate(inputs: &[&str], i: usize, x: f64) -> Option<f64> {
+        let resolved = resolve(inputs).remove(i).unwrap().unwrap();
+        generate_calculator(resolved.expression).unwrap().calculate(x)
+    }
+
+    fn error_code(inputs: &[&str], i: usize) -> &'static str {
+        resolve(inputs).remove(i).unwrap().unwrap_err().code()
+    }
+
+    #[test]
+    fn test_named_functions() {
+        let inputs = ["g(x) = x^2 + 1", "f1(x) + g(2x)", "", "f(g(x))", "f(x) = 3x"];
+        assert_eq!(calculate(&inputs, 0, 2.0), Some(5.0));
+        assert_eq!(calculate(&inputs, 1, 2.0), Some(22.0));
+        assert_eq!(calculate(&inputs, 3, 2.0), Some(15.0));
+        assert_eq!(calculate(&["x + 1", "f₁(2x)"], 1, 1.0), Some(3.0));
+        assert!(resolve(&inputs)[2].is_none());
+    }
+
+    #[test]
+    fn test_definition_parameter() {
+        //only the declared parameter is replaced by the argument of a call
+        assert_eq!(calculate(&["h(y) = 2y", "h(3)"], 1, 0.0), Some(6.0));
+        assert_eq!(calculate(&["h(y) = 2y", "h(x + 1)"], 1, 2.0), Some(6.0));
+        assert_eq!(calculate(&["h(y) = 2y"], 0, 3.0), Some(6.0));
+        assert_eq!(calculate(&["x = y^2", "f1(3)"], 1, 0.0), Some(9.0));
+        assert_eq!(error_code(&["h(y) = x + y"], 0), "unexpected_variable");
+        assert_eq!(error_code(&["g(x) = x + y"], 0), "unexpected_variable");
+    }
+
+    #[test]
+    fn test_parsed_echo() {
+        let parsed: Vec<String> = resolve(&["g(x) = x^2", "2g(x + 1)", "y = 2x"]).into_iter().map(|resolved| resolved.unwrap().unwrap().parsed).collect();
+        assert_eq!(parsed, ["g(x) = (x ^ 2)", "(2 * g((x + 1)))", "y = (2 * x)"]);
+    }
+
+    #[test]
+    fn test_parameters_are_not_calls() {
+        //a is only a function if some input defines it
+        assert_eq!(calculate(&["a(x + 1)"], 0, 1.0), Some(2.0));
+        let resolved = resolve(&["g(x) = a x", "g(2)"]).remove(1).unwrap().unwrap();
+        assert_eq!(generate_calculator(resolved.expression).unwrap().parameters(), ["a"]);
+    }
+
+    #[test]
+    fn test_equations() {
+        let kind = |input: &str| resolve(&[input]).remove(0).unwrap().unwrap().kind;
+        assert_eq!(kind("y = 2x + 1"), PlotKind::Function(Axis::X));
+        assert_eq!(kind("2x + 1 = y"), PlotKind::Function(Axis::X));
+        assert_eq!(kind("x = y^2"), PlotKind::Function(Axis::Y));
+        assert_eq!(kind("x = 3"), PlotKind::Function(Axis::Y));
+        assert_eq!(calculate(&["x = y^2 + 1"], 0, 2.0), Some(5.0));
+        //calling an equation calls the function it is graphed as
+        assert_eq!(calculate(&["y = 2x", "f1(x) + 1"], 1, 3.0), Some(7.0));
+        assert_eq!(error_code(&["2y + x"], 0), "unexpected_variable");
+        assert_eq!(error_code(&["y = f2(x)", "y"], 0), "invalid_reference");
+    }
+
+    #[test]
+    fn test_implicit_curves() {
+        let resolved = resolve(&["x^2 + y^2 = 25", "sin(x) = cos(y)", "y = y^2 + x", "f1(x)"]);
+        let kinds: Vec<PlotKind> = resolved[..3].iter().map(|resolved| resolved.as_ref().unwrap().as_ref().unwrap().kind).collect();
+        assert_eq!(kinds, [PlotKind::Implicit; 3]);
+        let expression = resolved[0].as_ref().unwrap().as_ref().unwrap().expression.clone();
+        assert_eq!(generate_calculator(expression).unwrap().calculate_point(3.0, 4.0), Some(0.0));
+        assert_eq!(resolved[3].as_ref().unwrap().as_ref().unwrap_err().code(), "not_a_function");
+    }
+
+    #[test]
+    fn test_inequalities() {
+        let kind = |input: &str| resolve(&[input]).remove(0).unwrap().unwrap().kind;
+        let region = |side, inclusive| Inequality { side, inclusive };
+        assert_eq!(kind("y < x^2"), PlotKind::FunctionRegion(Axis::X, region(Side::Less, false)));
+        assert_eq!(kind("y >= sin(x)"), PlotKind::FunctionRegion(Axis::X, region(Side::Greater, true)));
+        assert_eq!(kind("x^2 > y"), PlotKind::FunctionRegion(Axis::X, region(Side::Less, false)));
+        assert_eq!(kind("x ≤ y^2"), PlotKind::FunctionRegion(Axis::Y, region(Side::Less, true)));
+        assert_eq!(kind("x^2 + y^2 < 4"), PlotKind::ImplicitRegion(region(Side::Less, false)));
+        //a function region's expression is its boundary
+        assert_eq!(calculate(&["y < f2(x)", "g(x) = 2x"], 0, 1.0), Some(2.0));
+        assert_eq!(error_code(&["y < x", "f1(x)"], 1), "not_a_function");
+    }
+
+    #[test]
+    fn test_piecewise() {
+        assert_eq!(calculate(&["g(x) = {x < 0: -x, x}", "g(x - 2)"], 1, 1.0), Some(1.0));
+        assert_eq!(calculate(&["g(x) = x^2", "if(g(x) > 4, 1, 0)"], 1, 3.0), Some(1.0));
+        assert_eq!(calculate(&["y = x {x > 0}"], 0, -1.0), None);
+        assert_eq!(error_code(&["{y < 0: x, 1}"], 0), "unexpected_variable");
+    }
+
+    #[test]
+    fn test_points() {
+        let resolved = resolve(&["(1, g(2)), (a, 3)", "g(x) = x^2"]).remove(0).unwrap().unwrap();
+        assert_eq!(resolved.kind, PlotKind::Points);
+        assert_eq!(resolved.expression.to_string(), "(1, (2 ^ 2)), (a, 3)");
+        assert_eq!(error_code(&["(1, 2), (x, 3)"], 0), "unexpected_variable");
+        assert_eq!(error_code(&["(1, 2)", "f1(x) + 1"], 1), "not_a_function");
+        //a point used as a number is an error
+        let expression = resolve(&["2(1, 2)"]).remove(0).unwrap().unwrap().expression;
+        assert!(matches!(generate_calculator(expression), Err(EvalError::UnexpectedPoint { .. })));
+    }
+
+    #[test]
+    fn test_parametric() {
+        let coordinate = |expression: &str, t: f64| generate_calculator(resolve_parametric(expression).unwrap()).unwrap().calculate(t);
+        assert_eq!(coordinate("2t + 1", 3.0), Some(7.0));
+        assert_eq!(resolve_parametric("a cos(t)").unwrap().to_string(), "(a * cos(t))");
+        let error_code = |expression: &str| resolve_parametric(expression).unwrap_err().code();
+        assert_eq!(error_code("t + x"), "unexpected_variable");
+        assert_eq!(error_code("y = t"), "unexpected_token");
+        assert_eq!(error_code(""), "unexpected_end_of_input");
+    }
+
+    #[test]
+    fn test_theta_range() {
+        let turns = |input: &str| {
+            let (start, end) = default_theta_range(&resolve(&[input]).remove(0).unwrap().unwrap().expression);
+            assert_eq!(start, 0.0);
+            end / (2.0 * PI)
+        };
+        assert_eq!(turns("sin(5θ/3)"), 3.0);
+        assert_eq!(turns("cos(θ/2) + sin(2theta + 1)"), 2.0);
+        assert_eq!(turns("2 + sin(3x/4)^2"), 4.0);
+        assert_eq!(turns("sin(-0.4θ)"), 5.0);
+        //functions that don't repeat after a whole number of turns are graphed over one turn
+        assert_eq!(turns("θ/4"), 1.0);
+        assert_eq!(turns("sin(πθ)"), 1.0);
+        assert_eq!(turns("sin(θ) + θ"), 1.0);
+        assert_eq!(turns("3"), 1.0);
+    }
+
+    #[test]
+    fn test_theta_errors() {
+        assert_eq!(calculate(&["sin(2θ)", "f1(π/4)"], 1, 0.0), Some(1.0));
+        assert_eq!(calculate(&["g(θ) = θ/4", "g(2)"], 1, 0.0), Some(0.5));
+        for input in ["θ = 1", "x + θ", "y = θ x", "r = θ/4", "y < θ", "(θ, 1)", "g(x) = x + θ", "theta + y"] {
+            assert_eq!(error_code(&[input], 0), "unexpected_variable", "{}", input);
+        }
+        let error = resolve(&["r = θ/4"]).remove(0).unwrap().unwrap_err();
+        assert_eq!(error, EvalError::UnexpectedVariable { name: String::from("theta"), span: Span::new(4, 5) }.into());
+        assert_eq!(resolve_parametric("θ t").unwrap_err().code(), "unexpected_variable");
+    }
+
+    #[test]
+    fn test_definition_errors() {
+        assert_eq!(error_code(&["f2(x)", "f1(x)"], 0), "circular_definition");
+        assert_eq!(error_code(&["g(x) = g(x) + 1"], 0), "circular_definition");
+        assert_eq!(error_code(&["g(x) = h(x)", "h(x) = g(x)", "g(x) + 1"], 2), "invalid_reference");
+        assert_eq!(error_code(&["f3(x)", ""], 0), "undefined_function");
+        assert_eq!(error_code(&["g(x) = x", "g(x) = 2x"], 1), "duplicate_definition");
+        assert_eq!(error_code(&["f2(x) = x"], 0), "duplicate_definition");
+        assert_eq!(error_code(&["g(x) = x +", "g(1)"], 1), "invalid_reference");
+        assert_eq!(error_code(&["g(x) = "], 0), "unexpected_end_of_input");
+        //the error points at the call in the input that made it
+        let error = resolve(&["f2(x)", "f1(x)"]).remove(1).unwrap().unwrap_err();
+        assert_eq!(error.span(), Span::new(0, 5));
+    }
+}
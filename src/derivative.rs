@@ -0,0 +1,221 @@
+/*
+SYMBOLIC DIFFERENTIATION:
+The derivative of a function is found from its expanded AST, with the sum, product, quotient, power and chain rules and the derivative of
+each built in function, and is then simplified so that it reads the way it would be written by hand (see simplify.rs). Every variable is
+the variable a function is sampled along (see calculate in calculator.rs), so is differentiated to 1, and parameters and constants are
+differentiated to 0.
+
+Step functions, such as floor, and conditions are flat, so have a derivative of 0, and a piecewise expression is differentiated branch
+by branch. The derivative of max(a, b) is the derivative of whichever of a and b is larger. A factorial of an expression of the variable
+has no derivative in terms of the built in functions, so a function that uses one can't be differentiated.
+*/
+
+use crate::{parser::{ASTNode, ASTNodeType}, scanner::{Span, TokenType}, simplify::{binary, call, negate, number, simplify}};
+
+//function to produce the first order derivatives of an expression, up to the given order, simplified - stops early at one that can't be differentiated
+pub fn derivatives(expression: &ASTNode, order: usize) -> Vec<ASTNode> {
+    let mut derivatives: Vec<ASTNode> = Vec::new();
+    for _ in 0..order {
+        let Some(derivative) = differentiate(derivatives.last().unwrap_or(expression)) else { break; };
+        derivatives.push(simplify(derivative));
+    }
+    derivatives
+}
+
+//function to produce the unsimplified derivative of an expression - None if it can't be differentiated
+fn differentiate(expression: &ASTNode) -> Option<ASTNode> {
+    let span = expression.span;
+    if !uses_variable(expression) { return Some(number(0.0, span)); }
+    let derivative = match &expression.node_type {
+        ASTNodeType::AtomicExpression(_) => number(1.0, span),
+        ASTNodeType::BinaryExpression(operator, u, v) => {
+            let (u, v) = (u.as_ref().clone(), v.as_ref().clone());
+            let (du, dv) = (differentiate(&u)?, differentiate(&v)?);
+            match operator {
+                TokenType::Add | TokenType::Sub => binary(operator.clone(), du, dv, span),
+                TokenType::Mul => {
+                    //a constant factor stays as it is
+                    if !uses_variable(&u) { binary(TokenType::Mul, u, dv, span) }
+                    else if !uses_variable(&v) { binary(TokenType::Mul, du, v, span) }
+                    else { binary(TokenType::Add, binary(TokenType::Mul, du, v.clone(), span), binary(TokenType::Mul, u, dv, span), span) }
+                },
+                TokenType::Div => {
+                    if !uses_variable(&v) { binary(TokenType::Div, du, v, span) }
+                    else {
+                        let numerator = binary(TokenType::Sub, binary(TokenType::Mul, du, v.clone(), span), binary(TokenType::Mul, u, dv, span), span);
+                        binary(TokenType::Div, numerator, binary(TokenType::Exp, v, number(2.0, span), span), span)
+                    }
+                },
+                TokenType::Exp => {
+                    let power = binary(TokenType::Exp, u.clone(), v.clone(), span);
+                    if !uses_variable(&v) {
+                        //v u^(v - 1) u'
+                        let lowered = binary(TokenType::Exp, u, binary(TokenType::Sub, v.clone(), number(1.0, span), span), span);
+                        binary(TokenType::Mul, binary(TokenType::Mul, v, lowered, span), du, span)
+                    }
+                    else if !uses_variable(&u) {
+                        //u^v ln(u) v'
+                        binary(TokenType::Mul, binary(TokenType::Mul, power, call("ln", vec![u], span), span), dv, span)
+                    }
+                    else {
+                        //u^v (v' ln(u) + v u' / u)
+                        let through_power = binary(TokenType::Mul, dv, call("ln", vec![u.clone()], span), span);
+                        let through_base = binary(TokenType::Div, binary(TokenType::Mul, v, du, span), u, span);
+                        binary(TokenType::Mul, power, binary(TokenType::Add, through_power, through_base, span), span)
+                    }
+                },
+                TokenType::Mod => modulo_derivative(u, v, du, dv, span),
+                _ => return None
+            }
+        },
+        ASTNodeType::UnaryExpression(TokenType::Sub, u) => negate(differentiate(u)?, span),
+        ASTNodeType::UnaryExpression(_, _) => return None,
+        ASTNodeType::FunctionExpression(TokenType::FunctionName(name), arguments) => function_derivative(name, arguments, span)?,
+        ASTNodeType::FunctionExpression(_, _) => return None,
+        ASTNodeType::InequalityExpression(_, _, _) | ASTNodeType::LogicalExpression(_, _, _) => number(0.0, span),
+        ASTNodeType::PiecewiseExpression(branches, default) => {
+            let branches = branches.iter().map(|(condition, value)| Some((condition.clone(), differentiate(value)?))).collect::<Option<_>>()?;
+            let default = match default {
+                Some(default) => Some(Box::new(differentiate(default)?)),
+                None => None
+            };
+            ASTNode::new(ASTNodeType::PiecewiseExpression(branches, default), span)
+        },
+        ASTNodeType::PointExpression(_, _) | ASTNodeType::ListExpression(_) => return None
+    };
+    Some(derivative)
+}
+
+//function to differentiate a mod m, which is a - m floor(a / m)
+fn modulo_derivative(a: ASTNode, m: ASTNode, da: ASTNode, dm: ASTNode, span: Span) -> ASTNode {
+    let quotient = call("floor", vec![binary(TokenType::Div, a, m, span)], span);
+    binary(TokenType::Sub, da, binary(TokenType::Mul, dm, quotient, span), span)
+}
+
+//function to differentiate a call to a built in function, by the chain rule - None if an argument can't be differentiated
+fn function_derivative(name: &str, arguments: &[ASTNode], span: Span) -> Option<ASTNode> {
+    let u = arguments[0].clone();
+    let du = differentiate(&u)?;
+    let square = |u: ASTNode| binary(TokenType::Exp, u, number(2.0, span), span);
+    let reciprocal = |u: ASTNode| binary(TokenType::Div, number(1.0, span), u, span);
+    let outer = match name {
+        "sin" => call("cos", vec![u], span),
+        "cos" => negate(call("sin", vec![u], span), span),
+        "tan" => square(call("sec", vec![u], span)),
+        "sec" => binary(TokenType::Mul, call("sec", vec![u.clone()], span), call("tan", vec![u], span), span),
+        "csc" => negate(binary(TokenType::Mul, call("csc", vec![u.clone()], span), call("cot", vec![u], span), span), span),
+        "cot" => negate(square(call("csc", vec![u], span)), span),
+        "asin" => reciprocal(call("sqrt", vec![binary(TokenType::Sub, number(1.0, span), square(u), span)], span)),
+        "acos" => negate(reciprocal(call("sqrt", vec![binary(TokenType::Sub, number(1.0, span), square(u), span)], span)), span),
+        "atan" => reciprocal(binary(TokenType::Add, number(1.0, span), square(u), span)),
+        "sinh" => call("cosh", vec![u], span),
+        "cosh" => call("sinh", vec![u], span),
+        "tanh" => binary(TokenType::Sub, number(1.0, span), square(call("tanh", vec![u], span)), span),
+        "asinh" => reciprocal(call("sqrt", vec![binary(TokenType::Add, square(u), number(1.0, span), span)], span)),
+        "acosh" => reciprocal(call("sqrt", vec![binary(TokenType::Sub, square(u), number(1.0, span), span)], span)),
+        "atanh" => reciprocal(binary(TokenType::Sub, number(1.0, span), square(u), span)),
+        //log(b, x) is ln(x) / ln(b)
+        "log" if arguments.len() == 2 => {
+            let (base, x) = (u, arguments[1].clone());
+            return differentiate(&binary(TokenType::Div, call("ln", vec![x], span), call("ln", vec![base], span), span));
+        },
+        "log" => reciprocal(binary(TokenType::Mul, u, call("ln", vec![number(10.0, span)], span), span)),
+        "ln" => reciprocal(u),
+        "exp" => call("exp", vec![u], span),
+        "sqrt" => reciprocal(binary(TokenType::Mul, number(2.0, span), call("sqrt", vec![u], span), span)),
+        "cbrt" => reciprocal(binary(TokenType::Mul, number(3.0, span), square(call("cbrt", vec![u], span)), span)),
+        "abs" => call("sign", vec![u], span),
+        "floor" | "ceil" | "round" | "sign" => return Some(number(0.0, span)),
+        //max(a, b, ...) is a where a is at least max(b, ...), and max(b, ...) elsewhere
+        "max" | "min" => {
+            let rest = if arguments.len() == 2 { arguments[1].clone() } else { call(name, arguments[1..].to_vec(), span) };
+            let operator = if name == "max" { TokenType::GreaterEqual } else { TokenType::LessEqual };
+            let condition = ASTNode::new(ASTNodeType::InequalityExpression(operator, Box::new(u), Box::new(rest.clone())), span);
+            let branches = vec![(condition, du)];
+            return Some(ASTNode::new(ASTNodeType::PiecewiseExpression(branches, Some(Box::new(differentiate(&rest)?))), span));
+        },
+        "atan2" => {
+            //(x y' - y x') / (x^2 + y^2)
+            let (y, x, dy) = (u, arguments[1].clone(), du);
+            let dx = differentiate(&x)?;
+            let numerator = binary(TokenType::Sub, binary(TokenType::Mul, x.clone(), dy, span), binary(TokenType::Mul, y.clone(), dx, span), span);
+            return Some(binary(TokenType::Div, numerator, binary(TokenType::Add, square(x), square(y), span), span));
+        },
+        "root" => {
+            //the nth root of x is x^(1/n), so its derivative is root(n, x) (x' / (n x) - ln|x| n' / n^2)
+            let (n, x, dn) = (u, arguments[1].clone(), du);
+            let dx = differentiate(&x)?;
+            let root = call("root", vec![n.clone(), x.clone()], span);
+            let through_x = binary(TokenType::Div, dx, binary(TokenType::Mul, n.clone(), x.clone(), span), span);
+            let through_n = binary(TokenType::Div, binary(TokenType::Mul, call("ln", vec![call("abs", vec![x], span)], span), dn, span), square(n), span);
+            return Some(binary(TokenType::Mul, root, binary(TokenType::Sub, through_x, through_n, span), span));
+        },
+        "mod" => {
+            let m = arguments[1].clone();
+            let dm = differentiate(&m)?;
+            return Some(modulo_derivative(u, m, du, dm, span));
+        },
+        _ => return None
+    };
+    Some(binary(TokenType::Mul, outer, du, span))
+}
+
+//function to check whether an expression uses a variable anywhere
+fn uses_variable(expression: &ASTNode) -> bool {
+    match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::Variable(_)) => true,
+        _ => expression.children().into_iter().any(uses_variable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calculator::generate_calculator, parser::parse, scanner::{scan, RECOGNIZED_FUNCTIONS}};
+
+    fn derivative_strings(expression: &str) -> Vec<String> {
+        derivatives(&parse(&scan(expression).unwrap()).unwrap(), 2).iter().map(|derivative| derivative.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(derivative_strings("3x^2 + 2x + 1"), ["((6 * x) + 2)", "6"]);
+        assert_eq!(derivative_strings("x sin(x)"), ["(sin(x) + (x * cos(x)))", "((2 * cos(x)) - (x * sin(x)))"]);
+        assert_eq!(derivative_strings("1/x"), ["(-(1 / (x ^ 2)))", "((2 * x) / ((x ^ 2) ^ 2))"]);
+        assert_eq!(derivative_strings("sin(x)^2")[0], "(2 * (cos(x) * sin(x)))");
+        assert_eq!(derivative_strings("e^(2x)")[0], "(2 * (e ^ (2 * x)))");
+        assert_eq!(derivative_strings("2^x")[0], "((2 ^ x) * ln(2))");
+        assert_eq!(derivative_strings("a x^3 + b")[0], "(3 * (a * (x ^ 2)))");
+        assert_eq!(derivative_strings("cos(3x)"), ["(-3 * sin((3 * x)))", "(-9 * cos((3 * x)))"]);
+        assert_eq!(derivative_strings("{x < 0: -x, x^2}")[0], "{x < 0: -1, (2 * x)}");
+        assert_eq!(derivative_strings("floor(x) + 5"), ["0", "0"]);
+        //a factorial of the variable can't be differentiated, but a factorial of a constant can
+        assert!(derivative_strings("x! + x").is_empty());
+        assert_eq!(derivative_strings("3! x"), ["6", "0"]);
+    }
+
+    #[test]
+    fn test_every_function() {
+        //each derivative is checked against a central difference, at a point where every function is defined
+        let difference = |expression: &str, x: f64| {
+            let mut calculator = generate_calculator(parse(&scan(expression).unwrap()).unwrap()).unwrap();
+            let h = 1e-6;
+            (calculator.calculate(x + h).unwrap() - calculator.calculate(x - h).unwrap()) / (2.0 * h)
+        };
+        for name in RECOGNIZED_FUNCTIONS {
+            let arguments = match *name {
+                "max" | "min" => "x^2, 2x, 0.5",
+                "atan2" | "root" | "mod" | "log" => "x + 2, x^2 + 1",
+                "acosh" => "x + 1.5",
+                _ => "x/2"
+            };
+            let expression = format!("{}({})", name, arguments);
+            let derivative = derivatives(&parse(&scan(&expression).unwrap()).unwrap(), 1).remove(0);
+            for x in [0.3, 0.7] {
+                let actual = generate_calculator(derivative.clone()).unwrap().calculate(x).unwrap();
+                let expected = difference(&expression, x);
+                assert!((actual - expected).abs() < 1e-5, "{} at {}: expected {}, got {} from {}", expression, x, expected, actual, derivative);
+            }
+        }
+    }
+}
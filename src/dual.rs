@@ -0,0 +1,198 @@
+/*
+AUTOMATIC DIFFERENTIATION:
+A Dual is a value f(x) along with its derivative f′(x). Each operation here takes the values and derivatives of its operands and
+produces the value and derivative of its result by the chain rule, so that running an expression's instructions on duals (see
+calculate_derivative in calculator.rs) gives its exact derivative, rather than one estimated from nearby points. Values are calculated
+exactly as at a point, so are undefined in the same places. Step functions, such as floor and comparisons, have a derivative of 0, and
+abs has a derivative of 0 at 0.
+*/
+
+use std::f64::consts::{LN_10, PI};
+
+use crate::calculator::{factorial as factorial_at_point, floored_mod, Function};
+
+//a value and its derivative with respect to the variable
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Dual {
+    pub value: f64,
+    pub derivative: f64
+}
+
+impl Dual {
+    pub fn new(value: f64, derivative: f64) -> Dual {
+        Dual { value, derivative }
+    }
+
+    //a value that doesn't depend on the variable
+    pub fn constant(value: f64) -> Dual {
+        Dual::new(value, 0.0)
+    }
+}
+
+pub fn add(a: Dual, b: Dual) -> Dual {
+    Dual::new(a.value + b.value, a.derivative + b.derivative)
+}
+
+pub fn sub(a: Dual, b: Dual) -> Dual {
+    Dual::new(a.value - b.value, a.derivative - b.derivative)
+}
+
+pub fn mul(a: Dual, b: Dual) -> Dual {
+    Dual::new(a.value * b.value, a.derivative * b.value + a.value * b.derivative)
+}
+
+pub fn div(a: Dual, b: Dual) -> Option<Dual> {
+    if b.value == 0.0 { return None; }
+    Some(Dual::new(a.value / b.value, (a.derivative * b.value - a.value * b.derivative) / (b.value * b.value)))
+}
+
+/*function to differentiate base ^ power - the derivative through the power needs the log of the base, so is only defined for a positive
+base, or a base of 0 with a positive power, where that part of the derivative is 0*/
+pub fn pow(base: Dual, power: Dual) -> Option<Dual> {
+    let value = base.value.powf(power.value);
+    if value.is_nan() { return None; }
+    let through_base = if base.derivative == 0.0 { 0.0 } else { power.value * base.value.powf(power.value - 1.0) * base.derivative };
+    let through_power = if power.derivative == 0.0 { 0.0 }
+                        else if base.value > 0.0 { value * base.value.ln() * power.derivative }
+                        else if base.value == 0.0 && power.value > 0.0 { 0.0 }
+                        else { return None; };
+    Some(Dual::new(value, through_base + through_power))
+}
+
+//function to differentiate x mod m - x mod m is x - m floor(x / m), and floor has a derivative of 0
+pub fn modulo(x: Dual, m: Dual) -> Option<Dual> {
+    let value = floored_mod(x.value, m.value)?;
+    Some(Dual::new(value, x.derivative - m.derivative * (x.value / m.value).floor()))
+}
+
+//function to differentiate x! - the derivative of gamma(x + 1) is gamma(x + 1) digamma(x + 1)
+pub fn factorial(x: Dual) -> Option<Dual> {
+    let value = factorial_at_point(x.value)?;
+    Some(Dual::new(value, value * digamma(x.value + 1.0) * x.derivative))
+}
+
+/*function to approximate the digamma function, the derivative of ln(gamma(x)) - uses the reflection formula for x < 0.5, and the
+recurrence digamma(x) = digamma(x + 1) - 1/x to move x up to where the asymptotic series is accurate*/
+fn digamma(x: f64) -> f64 {
+    if x < 0.5 {
+        return digamma(1.0 - x) - PI / (PI * x).tan();
+    }
+    let mut x = x;
+    let mut result = 0.0;
+    while x < 6.0 {
+        result -= 1.0 / x;
+        x += 1.0;
+    }
+    let x2 = 1.0 / (x * x);
+    result + x.ln() - 0.5 / x - x2 * (1.0 / 12.0 - x2 * (1.0 / 120.0 - x2 * (1.0 / 252.0 - x2 * (1.0 / 240.0 - x2 / 132.0))))
+}
+
+/*function to differentiate a built in function at its one or two arguments - the value is calculated by Function::apply in calculator.rs, so
+is None in the same places. Max and min of more than two arguments are compiled into calculations of two at a time (see compile in
+calculator.rs)*/
+pub fn apply_function(function: Function, args: &[Dual]) -> Option<Dual> {
+    let values = [args[0].value, args.get(1).map_or(0.0, |arg| arg.value)];
+    let value = function.apply(&values[..args.len()])?;
+    let (u, du) = (args[0].value, args[0].derivative);
+    let derivative = match function {
+        Function::Sin => u.cos() * du,
+        Function::Cos => -u.sin() * du,
+        Function::Tan => du / (u.cos() * u.cos()),
+        Function::Sec => value * u.tan() * du,
+        Function::Csc => -value * du / u.tan(),
+        Function::Cot => -du / (u.sin() * u.sin()),
+        Function::Asin => du / (1.0 - u * u).sqrt(),
+        Function::Acos => -du / (1.0 - u * u).sqrt(),
+        Function::Atan => du / (1.0 + u * u),
+        Function::Sinh => u.cosh() * du,
+        Function::Cosh => u.sinh() * du,
+        Function::Tanh => (1.0 - value * value) * du,
+        Function::Asinh => du / (u * u + 1.0).sqrt(),
+        Function::Acosh => du / (u * u - 1.0).sqrt(),
+        Function::Atanh => du / (1.0 - u * u),
+        Function::Log if args.len() == 2 => {
+            //log(b, x) is ln(x) / ln(b)
+            let (b, x) = (args[0], args[1]);
+            (x.derivative / x.value - value * b.derivative / b.value) / b.value.ln()
+        },
+        Function::Log => du / (u * LN_10),
+        Function::Ln => du / u,
+        Function::Exp => value * du,
+        Function::Sqrt => du / (2.0 * value),
+        Function::Cbrt => du / (3.0 * value * value),
+        Function::Abs => if u == 0.0 { 0.0 } else { u.signum() * du },
+        Function::Floor | Function::Ceil | Function::Round | Function::Sign => 0.0,
+        //the derivative of whichever argument is the largest (or smallest), taking the first of any that are equal
+        Function::Max | Function::Min => args.iter().find(|arg| arg.value == value).map_or(0.0, |arg| arg.derivative),
+        Function::Atan2 => {
+            let (y, x) = (args[0], args[1]);
+            (x.value * y.derivative - y.value * x.derivative) / (x.value * x.value + y.value * y.value)
+        },
+        Function::Root => {
+            //the nth root of x is x^(1/n), for either sign of x
+            let (n, x) = (args[0], args[1]);
+            let through_x = if x.derivative == 0.0 { 0.0 } else { value / (n.value * x.value) * x.derivative };
+            let through_n = if n.derivative == 0.0 { 0.0 } else { -value * x.value.abs().ln() / (n.value * n.value) * n.derivative };
+            through_x + through_n
+        },
+        Function::Mod => return modulo(args[0], args[1])
+    };
+    Some(Dual::new(value, derivative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::RECOGNIZED_FUNCTIONS;
+
+    //function to estimate the derivative of a function of one variable with a central difference
+    fn estimate(f: impl Fn(f64) -> Option<f64>, x: f64) -> f64 {
+        let h = 1e-6;
+        (f(x + h).unwrap() - f(x - h).unwrap()) / (2.0 * h)
+    }
+
+    #[test]
+    fn test_every_function() {
+        //each argument is varied in turn, at a point where the function is defined and differentiable
+        for name in RECOGNIZED_FUNCTIONS {
+            let points = if *name == "acosh" { [1.7, 0.0] } else { [0.3, 2.7] };
+            let arg_count = if ["max", "min", "atan2", "root", "mod"].contains(name) { 2 } else { 1 };
+            for varied in 0..arg_count {
+                let args = |x: f64| -> Vec<Dual> {
+                    (0..arg_count).map(|i| if i == varied { Dual::new(x, 1.0) } else { Dual::constant(points[i]) }).collect()
+                };
+                let x = points[varied];
+                let function = Function::from_name(name).unwrap();
+                let actual = apply_function(function, &args(x)).unwrap();
+                let expected = estimate(|x| function.apply(&args(x).iter().map(|arg| arg.value).collect::<Vec<f64>>()), x);
+                assert!((actual.derivative - expected).abs() < 1e-5, "{} argument {}: expected {}, got {}", name, varied, expected, actual.derivative);
+            }
+        }
+    }
+
+    #[test]
+    fn test_two_argument_log() {
+        let args = [Dual::new(2.0, 1.0), Dual::constant(8.0)];
+        let expected = estimate(|b| Function::Log.apply(&[b, 8.0]), 2.0);
+        assert!((apply_function(Function::Log, &args).unwrap().derivative - expected).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_powers() {
+        assert_eq!(pow(Dual::new(3.0, 1.0), Dual::constant(2.0)), Some(Dual::new(9.0, 6.0)));
+        assert_eq!(pow(Dual::new(-2.0, 1.0), Dual::constant(3.0)), Some(Dual::new(-8.0, 12.0)));
+        let power = pow(Dual::constant(2.0), Dual::new(3.0, 1.0)).unwrap();
+        assert!((power.derivative - 8.0 * 2.0_f64.ln()).abs() < 1e-12);
+        assert_eq!(pow(Dual::constant(-2.0), Dual::new(3.0, 1.0)), None);
+        assert_eq!(pow(Dual::new(-2.0, 1.0), Dual::constant(0.5)), None);
+    }
+
+    #[test]
+    fn test_factorial() {
+        for x in [0.5, 2.0, 4.3, -0.5, -2.5] {
+            let expected = estimate(factorial_at_point, x);
+            let actual = factorial(Dual::new(x, 1.0)).unwrap().derivative;
+            assert!((actual - expected).abs() < 1e-5 * expected.abs().max(1.0), "{}: expected {}, got {}", x, expected, actual);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use std::fmt;
+use serde::Serialize;
+
+use crate::scanner::Span;
+
+/*errors are serialized for JS as objects tagged with a machine-readable code, e.g.
+{ "code": "unexpected_character", "character": "$", "span": { "start": 4, "end": 5 } } - Display produces an english message for each*/
+
+//errors produced while scanning an input string into tokens
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ScanError {
+    UnexpectedCharacter { character: char, span: Span },
+    UnknownIdentifier { identifier: String, span: Span },
+    UnknownFunction { name: String, span: Span },
+    InvalidNumber { literal: String, span: Span }
+}
+
+//errors produced while parsing tokens into an AST
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum ParseError {
+    UnexpectedEndOfInput { span: Span },
+    UnbalancedParenthesis { span: Span },
+    UnbalancedAbsoluteValue { span: Span },
+    UnbalancedBrace { span: Span },
+    UnexpectedToken { expected: String, found: String, span: Span },
+    ExpectedExpression { found: String, span: Span },
+    ExpectedCondition { span: Span },
+    ExpectedPoint { span: Span },
+    DivisionByZero { span: Span },
+    WrongArgumentCount { function: String, min: usize, max: Option<usize>, found: usize, span: Span }
+}
+
+//errors produced while resolving references between inputs (see definition.rs), or generating Calculator instructions from an AST
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum EvalError {
+    UnknownFunction { name: String, span: Span },
+    UnknownConstant { name: String, span: Span },
+    UnsupportedOperator { operator: String, span: Span },
+    UndefinedFunction { name: String, span: Span },
+    DuplicateDefinition { name: String, span: Span },
+    CircularDefinition { name: String, span: Span },
+    InvalidReference { name: String, span: Span },
+    NotAFunction { name: String, span: Span },
+    UnexpectedVariable { name: String, span: Span },
+    UnexpectedPoint { span: Span },
+    InvalidInstructions { span: Span }
+}
+
+//any error produced while turning an input string into a Calculator
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(untagged)]
+pub enum ExpressionError {
+    Scan(ScanError),
+    Parse(ParseError),
+    Eval(EvalError)
+}
+
+/*result of initializing a single expression, passed to JS - on success, expression echoes back the expression as it was parsed, fully
+parenthesized, and parameters lists the parameters it uses, and on failure code, message and span describe the error, and error holds
+its details (see above). Empty expressions succeed with an empty echo*/
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Diagnostic {
+    pub success: bool,
+    pub code: Option<&'static str>,
+    pub message: Option<String>,
+    pub span: Option<Span>,
+    pub error: Option<ExpressionError>,
+    pub expression: Option<String>,
+    pub parameters: Vec<String>,
+    //first and second derivatives of a function, as they were simplified - empty for anything else, or where it can't be differentiated
+    pub derivatives: Vec<String>
+}
+
+//result of initializing a parametric curve, passed to JS - one Diagnostic for each of its coordinates
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ParametricDiagnostic {
+    pub x: Diagnostic,
+    pub y: Diagnostic
+}
+
+impl Diagnostic {
+    pub fn success(expression: String, parameters: Vec<String>) -> Diagnostic {
+        Diagnostic { success: true, code: None, message: None, span: None, error: None, expression: Some(expression), parameters, derivatives: Vec::new() }
+    }
+
+    pub fn failure(error: ExpressionError) -> Diagnostic {
+        let span = error.span();
+        //report positions to users as 1-based columns
+        let message = format!("{} at column {}", error, span.start + 1);
+        Diagnostic { success: false, code: Some(error.code()), message: Some(message), span: Some(span), error: Some(error), expression: None, parameters: Vec::new(), derivatives: Vec::new() }
+    }
+}
+
+impl ScanError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ScanError::UnexpectedCharacter { .. } => "unexpected_character",
+            ScanError::UnknownIdentifier { .. } => "unknown_identifier",
+            ScanError::UnknownFunction { .. } => "unknown_function",
+            ScanError::InvalidNumber { .. } => "invalid_number"
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ScanError::UnexpectedCharacter { span, .. } | ScanError::UnknownIdentifier { span, .. } | ScanError::UnknownFunction { span, .. }
+            | ScanError::InvalidNumber { span, .. } => *span
+        }
+    }
+}
+
+impl ParseError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedEndOfInput { .. } => "unexpected_end_of_input",
+            ParseError::UnbalancedParenthesis { .. } => "unbalanced_parenthesis",
+            ParseError::UnbalancedAbsoluteValue { .. } => "unbalanced_absolute_value",
+            ParseError::UnbalancedBrace { .. } => "unbalanced_brace",
+            ParseError::UnexpectedToken { .. } => "unexpected_token",
+            ParseError::ExpectedExpression { .. } => "expected_expression",
+            ParseError::ExpectedCondition { .. } => "expected_condition",
+            ParseError::ExpectedPoint { .. } => "expected_point",
+            ParseError::DivisionByZero { .. } => "division_by_zero",
+            ParseError::WrongArgumentCount { .. } => "wrong_argument_count"
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedEndOfInput { span } | ParseError::UnbalancedParenthesis { span } | ParseError::UnbalancedAbsoluteValue { span }
+            | ParseError::UnbalancedBrace { span } | ParseError::UnexpectedToken { span, .. } | ParseError::ExpectedCondition { span }
+            | ParseError::ExpectedPoint { span }
+            | ParseError::ExpectedExpression { span, .. } | ParseError::DivisionByZero { span } | ParseError::WrongArgumentCount { span, .. } => *span
+        }
+    }
+}
+
+impl EvalError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EvalError::UnknownFunction { .. } => "unknown_function",
+            EvalError::UnknownConstant { .. } => "unknown_constant",
+            EvalError::UnsupportedOperator { .. } => "unsupported_operator",
+            EvalError::UndefinedFunction { .. } => "undefined_function",
+            EvalError::DuplicateDefinition { .. } => "duplicate_definition",
+            EvalError::CircularDefinition { .. } => "circular_definition",
+            EvalError::InvalidReference { .. } => "invalid_reference",
+            EvalError::NotAFunction { .. } => "not_a_function",
+            EvalError::UnexpectedVariable { .. } => "unexpected_variable",
+            EvalError::UnexpectedPoint { .. } => "unexpected_point",
+            EvalError::InvalidInstructions { .. } => "invalid_instructions"
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            EvalError::UnknownFunction { span, .. } | EvalError::UnknownConstant { span, .. } | EvalError::UnsupportedOperator { span, .. }
+            | EvalError::UndefinedFunction { span, .. } | EvalError::DuplicateDefinition { span, .. } | EvalError::CircularDefinition { span, .. }
+            | EvalError::InvalidReference { span, .. } | EvalError::NotAFunction { span, .. } | EvalError::UnexpectedVariable { span, .. }
+            | EvalError::UnexpectedPoint { span } | EvalError::InvalidInstructions { span } => *span
+        }
+    }
+}
+
+impl ExpressionError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ExpressionError::Scan(e) => e.code(),
+            ExpressionError::Parse(e) => e.code(),
+            ExpressionError::Eval(e) => e.code()
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ExpressionError::Scan(e) => e.span(),
+            ExpressionError::Parse(e) => e.span(),
+            ExpressionError::Eval(e) => e.span()
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScanError::UnexpectedCharacter { character, .. } => write!(f, "unexpected character '{}'", character),
+            ScanError::UnknownIdentifier { identifier, .. } => write!(f, "unknown identifier '{}'", identifier),
+            ScanError::UnknownFunction { name, .. } => write!(f, "unknown function '{}'", name),
+            ScanError::InvalidNumber { literal, .. } => write!(f, "invalid number '{}'", literal)
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEndOfInput { .. } => write!(f, "unexpected end of input"),
+            ParseError::UnbalancedParenthesis { .. } => write!(f, "unbalanced parenthesis"),
+            ParseError::UnbalancedAbsoluteValue { .. } => write!(f, "unbalanced absolute value bar"),
+            ParseError::UnbalancedBrace { .. } => write!(f, "unbalanced brace"),
+            ParseError::UnexpectedToken { expected, found, .. } => write!(f, "expected {}, found {}", expected, found),
+            ParseError::ExpectedExpression { found, .. } => write!(f, "expected expression, found {}", found),
+            ParseError::ExpectedCondition { .. } => write!(f, "expected a condition such as x < 0"),
+            ParseError::ExpectedPoint { .. } => write!(f, "expected a point such as (1, 2)"),
+            ParseError::DivisionByZero { .. } => write!(f, "division by 0"),
+            ParseError::WrongArgumentCount { function, min, max, found, .. } => {
+                let arguments = |n: usize| if n == 1 { "argument" } else { "arguments" };
+                match max {
+                    Some(max) if max == min => write!(f, "{} expects {} {}, found {}", function, min, arguments(*min), found),
+                    Some(max) => write!(f, "{} expects {} to {} arguments, found {}", function, min, max, found),
+                    None => write!(f, "{} expects at least {} {}, found {}", function, min, arguments(*min), found)
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::UnknownFunction { name, .. } => write!(f, "unknown function '{}'", name),
+            EvalError::UnknownConstant { name, .. } => write!(f, "unknown constant '{}'", name),
+            EvalError::UnsupportedOperator { operator, .. } => write!(f, "unsupported operator '{}'", operator),
+            EvalError::UndefinedFunction { name, .. } => write!(f, "function '{}' is not defined", name),
+            EvalError::DuplicateDefinition { name, .. } => write!(f, "function '{}' is already defined", name),
+            EvalError::CircularDefinition { name, .. } => write!(f, "circular definition: '{}' depends on itself", name),
+            EvalError::InvalidReference { name, .. } => write!(f, "function '{}' has an error", name),
+            EvalError::NotAFunction { name, .. } => write!(f, "'{}' is not a function, so it can't be called", name),
+            EvalError::UnexpectedVariable { name, .. } => write!(f, "unexpected variable '{}'", name),
+            EvalError::UnexpectedPoint { .. } => write!(f, "a point can only be graphed on its own or in a list of points"),
+            EvalError::InvalidInstructions { .. } => write!(f, "the expression couldn't be compiled into valid instructions")
+        }
+    }
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpressionError::Scan(e) => write!(f, "{}", e),
+            ExpressionError::Parse(e) => write!(f, "{}", e),
+            ExpressionError::Eval(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl From<ScanError> for ExpressionError {
+    fn from(e: ScanError) -> ExpressionError {
+        ExpressionError::Scan(e)
+    }
+}
+
+impl From<ParseError> for ExpressionError {
+    fn from(e: ParseError) -> ExpressionError {
+        ExpressionError::Parse(e)
+    }
+}
+
+impl From<EvalError> for ExpressionError {
+    fn from(e: EvalError) -> ExpressionError {
+        ExpressionError::Eval(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_codes() {
+        let span = Span::new(0, 1);
+        let errors: Vec<(ExpressionError, &str)> = vec![
+            (ScanError::UnexpectedCharacter { character: '$', span }.into(), "unexpected_character"),
+            (ScanError::UnknownIdentifier { identifier: String::from("foo"), span }.into(), "unknown_identifier"),
+            (ScanError::UnknownFunction { name: String::from("sinh"), span }.into(), "unknown_function"),
+            (ScanError::InvalidNumber { literal: String::from("1.2.3"), span }.into(), "invalid_number"),
+            (ParseError::UnexpectedEndOfInput { span }.into(), "unexpected_end_of_input"),
+            (ParseError::UnbalancedParenthesis { span }.into(), "unbalanced_parenthesis"),
+            (ParseError::UnbalancedAbsoluteValue { span }.into(), "unbalanced_absolute_value"),
+            (ParseError::UnbalancedBrace { span }.into(), "unbalanced_brace"),
+            (ParseError::UnexpectedToken { expected: String::from("("), found: String::from("x"), span }.into(), "unexpected_token"),
+            (ParseError::ExpectedExpression { found: String::from("*"), span }.into(), "expected_expression"),
+            (ParseError::ExpectedCondition { span }.into(), "expected_condition"),
+            (ParseError::ExpectedPoint { span }.into(), "expected_point"),
+            (ParseError::DivisionByZero { span }.into(), "division_by_zero"),
+            (ParseError::WrongArgumentCount { function: String::from("sin"), min: 1, max: Some(1), found: 2, span }.into(), "wrong_argument_count"),
+            (EvalError::UnknownFunction { name: String::from("f"), span }.into(), "unknown_function"),
+            (EvalError::UnknownConstant { name: String::from("c"), span }.into(), "unknown_constant"),
+            (EvalError::UnsupportedOperator { operator: String::from("("), span }.into(), "unsupported_operator"),
+            (EvalError::UndefinedFunction { name: String::from("f3"), span }.into(), "undefined_function"),
+            (EvalError::DuplicateDefinition { name: String::from("g"), span }.into(), "duplicate_definition"),
+            (EvalError::CircularDefinition { name: String::from("g"), span }.into(), "circular_definition"),
+            (EvalError::InvalidReference { name: String::from("g"), span }.into(), "invalid_reference"),
+            (EvalError::NotAFunction { name: String::from("f1"), span }.into(), "not_a_function"),
+            (EvalError::UnexpectedVariable { name: String::from("y"), span }.into(), "unexpected_variable"),
+            (EvalError::UnexpectedPoint { span }.into(), "unexpected_point"),
+            (EvalError::InvalidInstructions { span }.into(), "invalid_instructions")
+        ];
+        for (error, code) in errors {
+            let json = serde_json::to_value(&error).unwrap();
+            assert_eq!(json["code"], code);
+            assert_eq!(error.code(), code);
+            assert_eq!(json["span"]["start"], 0);
+            assert_eq!(json["span"]["end"], 1);
+        }
+    }
+
+    #[test]
+    fn test_serialized_fields() {
+        let error: ExpressionError = ScanError::UnknownIdentifier { identifier: String::from("foo"), span: Span::new(4, 7) }.into();
+        let expected = serde_json::json!({ "code": "unknown_identifier", "identifier": "foo", "span": { "start": 4, "end": 7 } });
+        assert_eq!(serde_json::to_value(&error).unwrap(), expected);
+        assert_eq!(error.to_string(), "unknown identifier 'foo'");
+    }
+
+    #[test]
+    fn test_argument_count_messages() {
+        let span = Span::new(0, 1);
+        let message = |function: &str, min: usize, max: Option<usize>, found: usize| {
+            ParseError::WrongArgumentCount { function: String::from(function), min, max, found, span }.to_string()
+        };
+        assert_eq!(message("sin", 1, Some(1), 2), "sin expects 1 argument, found 2");
+        assert_eq!(message("log", 1, Some(2), 3), "log expects 1 to 2 arguments, found 3");
+        assert_eq!(message("max", 2, None, 1), "max expects at least 2 arguments, found 1");
+    }
+
+    #[test]
+    fn test_diagnostic_failure() {
+        let error = crate::scanner::scan("2 + sech(x)").unwrap_err();
+        let diagnostic = Diagnostic::failure(error.into());
+        assert!(!diagnostic.success);
+        assert_eq!(diagnostic.code, Some("unknown_function"));
+        assert_eq!(diagnostic.message.as_deref(), Some("unknown function 'sech' at column 5"));
+        assert_eq!(diagnostic.span, Some(Span::new(4, 8)));
+        let json = serde_json::to_value(&diagnostic).unwrap();
+        assert_eq!(json["error"]["name"], "sech");
+    }
+}
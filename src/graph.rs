@@ -1,11 +1,124 @@
-use std::f64::consts::PI;
 use web_sys::CanvasRenderingContext2d;
 use crate::calculator::Calculator;
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
 
 //colors used to draw function graphs
 const COLORS : &[& str] = &["red", "green", "blue", "purple", "navy"];
 
+/*a point calculated for a function, as (sampled coordinate, value, joined) - joined is whether the graph is unbroken from the point one 
+step before it, which is false across a pole or a jump*/
+pub type CachedPoint = (f64, Option<f64>, bool);
+
+//axis a function is sampled along - functions of y, as in x = g(y), are sampled along the y-axis
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Axis {
+    X,
+    Y
+}
+
+impl Axis {
+    //function to turn a sampled coordinate and the function's value there into an (x, y) point
+    pub fn point(self, sampled: f64, value: f64) -> (f64, f64) {
+        match self {
+            Axis::X => (sampled, value),
+            Axis::Y => (value, sampled)
+        }
+    }
+
+    //function to reorder viewport bounds into (sampled start, sampled end, value start, value end)
+    pub fn bounds(self, x_start: f64, x_end: f64, y_start: f64, y_end: f64) -> (f64, f64, f64, f64) {
+        match self {
+            Axis::X => (x_start, x_end, y_start, y_end),
+            Axis::Y => (y_start, y_end, x_start, x_end)
+        }
+    }
+}
+
+//shape of the marker drawn at each point of a list of points
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum MarkerShape {
+    #[default]
+    Circle,
+    Square,
+    Triangle,
+    Cross
+}
+
+//how a function is plotted
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PlotKind {
+    //a function of one variable, sampled along the given axis
+    Function(Axis),
+    //the curve where a function of both x and y is 0
+    Implicit,
+    //the region on one side of a function of one variable, sampled along the given axis
+    FunctionRegion(Axis, Inequality),
+    //the region where a function of both x and y is on one side of 0
+    ImplicitRegion(Inequality),
+    //a marker at each of a list of points
+    Points
+}
+
+/*which side of its boundary a region is on, and whether it includes its boundary - for a function region, Greater is the region where 
+the coordinate on the other axis is greater than the function, as in y > f(x) or x > g(y), and for an implicit region, Greater is the
+region where the function is greater than 0*/
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Inequality {
+    pub side: Side,
+    pub inclusive: bool
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Side {
+    Greater,
+    Less
+}
+
+impl Inequality {
+    //function to check whether a value is in the region, given the value it is compared against
+    fn holds(self, value: f64, boundary: f64) -> bool {
+        match (self.side, self.inclusive) {
+            (Side::Greater, false) => value > boundary,
+            (Side::Greater, true) => value >= boundary,
+            (Side::Less, false) => value < boundary,
+            (Side::Less, true) => value <= boundary
+        }
+    }
+}
+
+//number of cells across each side of the viewport in the coarse grid used to find implicit curves, and number of subcells across each side of a coarse cell the curve passes through
+const IMPLICIT_GRID_SIZE: usize = 64;
+const IMPLICIT_SUBDIVISIONS: usize = 4;
+//number of cells across each side of the viewport in the grid used to fill implicit regions
+const IMPLICIT_REGION_GRID_SIZE: usize = 175;
+//number of steps between the points sampled to fill a function region
+const REGION_STEP_FACTOR: usize = 4;
+//opacity of region fills, so that the graphs and grid under them still show
+const REGION_ALPHA: f64 = 0.25;
+//number of dashes across the viewport in the boundary of a region that doesn't include its boundary
+const BOUNDARY_DASHES: f64 = 120.0;
+//opacity of each order of derivative drawn under a function, starting from the function itself, so that each is a lighter shade of its color
+const DERIVATIVE_ALPHAS: [f64; 3] = [1.0, 0.55, 0.3];
+
+//number of bisection steps used to refine where an implicit curve crosses a cell edge
+const IMPLICIT_REFINEMENT_STEPS: usize = 6;
+//number of equal steps the range of t of a parametric curve is divided into, before any step is refined
+const PARAMETRIC_STEPS: usize = 256;
+//number of times a step of a parametric curve can be halved
+const PARAMETRIC_MAX_DEPTH: usize = 10;
+//longest a step of a parametric curve can be, as a fraction of the viewport's size, before it is halved
+const PARAMETRIC_MAX_STEP: f64 = 0.004;
+//most points calculated for a polar function, however long its range of theta
+const MAX_POLAR_STEPS: usize = 100000;
+//distance from the center of a marker to its edge, as a fraction of the viewport's size
+const MARKER_SIZE: f64 = 0.008;
+//number of steps bounded at once while looking for where a function enters the viewport
+const SKIP_STEPS: usize = 64;
+/*steepest a function's graph can be between neighbouring samples without bounding it between them to check for a break - there are several
+samples to a pixel, so at this slope neighbouring samples are about a pixel apart*/
+const MAX_JOINED_SLOPE: f64 = 8.0;
+
 //function to get the canvas element from HTML document and set up a rendering context for it
 pub fn initialize_canvas() -> (web_sys::HtmlCanvasElement, CanvasRenderingContext2d) {
     let document = web_sys::window().unwrap().document().unwrap();
@@ -43,7 +156,7 @@ pub fn transform_canvas(canvas: &web_sys::HtmlCanvasElement, rendering_context:
 
 //function to draw grid lines on canvas
 pub fn draw_initial_grid(rendering_context: &CanvasRenderingContext2d, x_start: f64, x_end: f64, y_start: f64, y_end: f64, step_size: usize) {
-    rendering_context.set_stroke_style(&"gray".into());
+    rendering_context.set_stroke_style_str("gray");
     let start = (x_start + 1.0).floor() as i32;
     let end = x_end.ceil() as i32;
     //use 1px and 2px lines
@@ -76,15 +189,26 @@ pub fn draw_initial_grid(rendering_context: &CanvasRenderingContext2d, x_start:
     rendering_context.stroke();
 }
 
-//function to draw a given function on canvas based on the values provided by the Calculator struct representing that function - caches values for future drawing
-pub fn draw_function_graph(rendering_context: &CanvasRenderingContext2d, calculator: &mut Calculator, cache: &mut Vec<(f64, Option<f64>)>, x_start: f64, x_end: f64, y_start: f64, y_end: f64, step_size: f64, idx: usize) {
-    rendering_context.set_stroke_style(&COLORS[idx % COLORS.len()].into());
+/*function to draw a given function on canvas based on the values provided by the Calculator struct representing that function - caches values for future drawing.
+The function is sampled along the given axis, so below, x is the sampled coordinate and y the function's value*/
+#[allow(clippy::too_many_arguments)]
+pub fn draw_function_graph(rendering_context: &CanvasRenderingContext2d, calculator: &mut Calculator, cache: &mut Vec<CachedPoint>, x_start: f64, x_end: f64, y_start: f64, y_end: f64, step_size: f64, idx: usize, axis: Axis) {
+    rendering_context.set_stroke_style_str(COLORS[idx % COLORS.len()]);
+    let (x_start, x_end, y_start, y_end) = axis.bounds(x_start, x_end, y_start, y_end);
+    let move_to = |x, y| { let (x, y) = axis.point(x, y); rendering_context.move_to(x, y); };
+    let line_to = |x, y| { let (x, y) = axis.point(x, y); rendering_context.line_to(x, y); };
     let mut x = x_start;
     let mut y;
     //find first point that is within our graph area and set x to it
     while x <= x_end {
+        //skip SKIP_STEPS steps at once where the function is undefined or outside the viewport throughout them
+        let run = step_size * SKIP_STEPS as f64;
+        if calculator.calculate_interval(x, x + run).is_none_or(|bounds| bounds.hi < y_start || bounds.lo > y_end) {
+            x += run - step_size;
+            continue;
+        }
         let next_y = calculator.calculate(x + step_size);
-        if next_y == None {
+        if next_y.is_none() {
             x += step_size;
             continue;
         }
@@ -101,33 +225,38 @@ pub fn draw_function_graph(rendering_context: &CanvasRenderingContext2d, calcula
     //represents whether the last point we calculated was inside our current graph viewport or not
     let mut in_graph_area = false;
     rendering_context.begin_path();
+    //value of the function at the last sampled point, before x
+    let mut last_y = calculator.calculate(x - step_size);
     while x <= x_end {
         y = calculator.calculate(x);
+        let joined = is_joined(calculator, (x - step_size, last_y), (x, y));
+        last_y = y;
         //cache point
-        cache.push((x, y));
+        cache.push((x, y, joined));
         match y {
             Some(val) => {
                 if val < y_start || val > y_end {
-                    if in_graph_area {
-                        rendering_context.line_to(x, val);
+                    if in_graph_area && joined {
+                        line_to(x, val);
                         in_graph_area = false;
                     }
                     else {
+                        in_graph_area = false;
                         let next_y = calculator.calculate(x + step_size);
                         if let Some(next_val) = next_y {
-                            if next_val > y_start && next_val < y_end {
+                            if next_val > y_start && next_val < y_end && is_joined(calculator, (x, y), (x + step_size, next_y)) {
                                 in_graph_area = true;
-                                rendering_context.move_to(x, val);
+                                move_to(x, val);
                             }
                         }
                     }
                 }
                 else {
-                    if !in_graph_area {
+                    if !in_graph_area || !joined {
                         in_graph_area = true;
-                        rendering_context.move_to(x, val);
+                        move_to(x, val);
                     }
-                    else { rendering_context.line_to(x, val); }
+                    else { line_to(x, val); }
                 }
             },
             None => {
@@ -139,15 +268,121 @@ pub fn draw_function_graph(rendering_context: &CanvasRenderingContext2d, calcula
     rendering_context.stroke();
 }
 
+/*function to check whether the graph of the function represented by a Calculator struct is unbroken between two neighbouring samples, given
+as (x, f(x)) - the function is only bounded between them where the samples suggest a break, where they differ in sign or by a steep jump*/
+pub fn is_joined(calculator: &mut Calculator, (start, start_y): (f64, Option<f64>), (end, end_y): (f64, Option<f64>)) -> bool {
+    let (Some(start_y), Some(end_y)) = (start_y, end_y) else { return false; };
+    if (start_y < 0.0) == (end_y < 0.0) && (end_y - start_y).abs() <= MAX_JOINED_SLOPE * (end - start) { return true; }
+    calculator.calculate_interval(start, end).is_some_and(|bounds| bounds.continuous)
+}
+
+/*function to fill the region on one side of a given function, sampled along the given axis - only fills the region, so the boundary is
+drawn separately, as for any other function. Where the function is undefined, the region is undefined too, so it is left unfilled*/
+#[allow(clippy::too_many_arguments)]
+pub fn draw_function_region(rendering_context: &CanvasRenderingContext2d, calculator: &mut Calculator, x_start: f64, x_end: f64, y_start: f64, y_end: f64, step_size: f64, idx: usize, axis: Axis, inequality: Inequality) {
+    rendering_context.set_fill_style_str(COLORS[idx % COLORS.len()]);
+    rendering_context.set_global_alpha(REGION_ALPHA);
+    let (start, end, value_start, value_end) = axis.bounds(x_start, x_end, y_start, y_end);
+    //the region extends to the edge of the viewport on its side of the function
+    let edge = if inequality.side == Side::Greater { value_end } else { value_start };
+    let step_size = step_size * REGION_STEP_FACTOR as f64;
+    let mut s = start;
+    //start of the current run of points where the function is defined, if we're in one
+    let mut run_start = None;
+    let mut last = start;
+    rendering_context.begin_path();
+    while s <= end + step_size {
+        match calculator.calculate(s) {
+            Some(value) => {
+                //clamp values to just outside the viewport, so that very large values don't distort the path
+                let value = value.clamp(value_start - 1.0, value_end + 1.0);
+                if run_start.is_none() {
+                    run_start = Some(s);
+                    let (x, y) = axis.point(s, edge);
+                    rendering_context.move_to(x, y);
+                }
+                let (x, y) = axis.point(s, value);
+                rendering_context.line_to(x, y);
+                last = s;
+            }
+            None => {
+                if run_start.take().is_some() { close_region(rendering_context, axis, last, edge); }
+            }
+        }
+        s += step_size;
+    }
+    if run_start.is_some() { close_region(rendering_context, axis, last, edge); }
+    rendering_context.fill();
+    rendering_context.set_global_alpha(1.0);
+}
+
+//function to close the outline of part of a function region, ending at sampled coordinate s, back along the edge of the viewport
+fn close_region(rendering_context: &CanvasRenderingContext2d, axis: Axis, s: f64, edge: f64) {
+    let (x, y) = axis.point(s, edge);
+    rendering_context.line_to(x, y);
+    rendering_context.close_path();
+}
+
+/*function to fill the region where the function represented by a Calculator struct is on one side of 0, within the current graph viewport -
+fills each run of grid cells whose centers are in the region with a single rectangle*/
+#[allow(clippy::too_many_arguments)]
+pub fn draw_implicit_region(rendering_context: &CanvasRenderingContext2d, calculator: &mut Calculator, x_start: f64, x_end: f64, y_start: f64, y_end: f64, idx: usize, inequality: Inequality) {
+    rendering_context.set_fill_style_str(COLORS[idx % COLORS.len()]);
+    rendering_context.set_global_alpha(REGION_ALPHA);
+    let n = IMPLICIT_REGION_GRID_SIZE;
+    let cell_width = (x_end - x_start) / n as f64;
+    let cell_height = (y_end - y_start) / n as f64;
+    for j in 0..n {
+        let y = y_start + (j as f64 + 0.5) * cell_height;
+        let mut run_start = None;
+        for i in 0..=n {
+            let x = x_start + (i as f64 + 0.5) * cell_width;
+            let in_region = i < n && calculator.calculate_point(x, y).is_some_and(|value| inequality.holds(value, 0.0));
+            match (in_region, run_start) {
+                (true, None) => run_start = Some(i),
+                (false, Some(start)) => {
+                    //overlap neighbouring rectangles slightly, so that no seams show between them
+                    let left = x_start + start as f64 * cell_width;
+                    rendering_context.fill_rect(left, y - cell_height / 2.0, (i - start) as f64 * cell_width, cell_height * 1.01);
+                    run_start = None;
+                }
+                _ => ()
+            }
+        }
+    }
+    rendering_context.set_global_alpha(1.0);
+}
+
+/*function to set the line style for the boundary of a region - dashed if the region doesn't include its boundary, and solid otherwise. 
+Passing None resets the line style for ordinary graphs*/
+pub fn set_boundary_style(rendering_context: &CanvasRenderingContext2d, inequality: Option<Inequality>, x_start: f64, x_end: f64) {
+    let dashes = js_sys::Array::new();
+    if let Some(Inequality { inclusive: false, .. }) = inequality {
+        let dash = (x_end - x_start) / BOUNDARY_DASHES;
+        dashes.push(&JsValue::from(dash));
+        dashes.push(&JsValue::from(dash));
+    }
+    rendering_context.set_line_dash(&dashes).unwrap();
+}
+
+/*function to set the shade used to draw the derivative of the given order of a function, lighter for each order - passing 0 resets the
+shade for ordinary graphs*/
+pub fn set_derivative_style(rendering_context: &CanvasRenderingContext2d, order: usize) {
+    rendering_context.set_global_alpha(DERIVATIVE_ALPHAS[order.min(DERIVATIVE_ALPHAS.len() - 1)]);
+}
+
 //function to draw a given function on canvas using polar coordinates based on the values provided by the Calculator struct representing that function - does not cache values
-pub fn draw_function_graph_polar(rendering_context: &CanvasRenderingContext2d, calculator: &mut Calculator, x_start: f64, x_end: f64, y_start: f64, y_end: f64, step_size: f64, idx: usize) {
-    rendering_context.set_stroke_style(&COLORS[idx % COLORS.len()].into());
-    let mut theta = 0.0;
+#[allow(clippy::too_many_arguments)]
+pub fn draw_function_graph_polar(rendering_context: &CanvasRenderingContext2d, calculator: &mut Calculator, x_start: f64, x_end: f64, y_start: f64, y_end: f64, step_size: f64, idx: usize, theta_start: f64, theta_end: f64) {
+    rendering_context.set_stroke_style_str(COLORS[idx % COLORS.len()]);
+    //long ranges of theta are sampled more coarsely, so that they don't take too long to draw
+    let step_size = step_size.max((theta_end - theta_start) / MAX_POLAR_STEPS as f64);
+    let mut theta = theta_start;
     let mut r;
     //find first point that is within our graph area and set x to it
-    while theta <= 2.0 * PI {
+    while theta <= theta_end {
         let next_r = calculator.calculate(theta + step_size);
-        if next_r == None {
+        if next_r.is_none() {
             theta += step_size;
             continue;
         }
@@ -167,10 +402,10 @@ pub fn draw_function_graph_polar(rendering_context: &CanvasRenderingContext2d, c
     //represents whether the last point we calculated was inside our current graph viewport or not
     let mut in_graph_area = false;
     rendering_context.begin_path();
-    while theta <= 2.0 * PI {
+    while theta <= theta_end {
         r = calculator.calculate(theta);
         //convert to cartesian coordinates
-        let (x, y) = if r == None { (0.0, None) } else { (theta.cos() * r.unwrap(), Some(theta.sin() * r.unwrap())) };
+        let (x, y) = match r { Some(r) => (theta.cos() * r, Some(theta.sin() * r)), None => (0.0, None) };
         match y {
             Some(val) => {
                 if val < y_start || val > y_end || x < x_start || x > x_end {
@@ -208,17 +443,20 @@ pub fn draw_function_graph_polar(rendering_context: &CanvasRenderingContext2d, c
     rendering_context.stroke();
 }
 
-//function to draw a given function on canvas based on the values provided in that function's cache
-pub fn draw_function_graph_from_cache(rendering_context: &CanvasRenderingContext2d, cache: &Vec<(f64, Option<f64>)>, x_start: f64, x_end: f64, y_start: f64, y_end: f64, step_size: f64, idx: usize) {
-    rendering_context.set_stroke_style(&COLORS[idx % COLORS.len()].into());
+//function to draw a given function on canvas based on the values provided in that function's cache - as in draw_function_graph, x is the sampled coordinate
+#[allow(clippy::too_many_arguments)]
+pub fn draw_function_graph_from_cache(rendering_context: &CanvasRenderingContext2d, cache: &[CachedPoint], x_start: f64, x_end: f64, y_start: f64, y_end: f64, step_size: f64, idx: usize, axis: Axis) {
+    rendering_context.set_stroke_style_str(COLORS[idx % COLORS.len()]);
+    let (x_start, x_end, y_start, y_end) = axis.bounds(x_start, x_end, y_start, y_end);
+    let move_to = |x, y| { let (x, y) = axis.point(x, y); rendering_context.move_to(x, y); };
+    let line_to = |x, y| { let (x, y) = axis.point(x, y); rendering_context.line_to(x, y); };
     //index into cache
     let mut i = ((x_start - cache[0].0) / step_size).floor() as usize;
     let mut x = cache[i].0;
-    let mut y;
     //find first point that is within our graph area and set x to it, set i to that cache index
     while x <= x_end && i + 1 < cache.len() {
         let next_y = cache[i + 1].1;
-        if next_y == None {
+        if next_y.is_none() {
             i += 1;
             x = cache[i].0;
             continue;
@@ -238,30 +476,31 @@ pub fn draw_function_graph_from_cache(rendering_context: &CanvasRenderingContext
     let mut in_graph_area = false;
     rendering_context.begin_path();
     while x <= x_end && i + 1 < cache.len() {
-        y = cache[i].1;
+        let (_, y, joined) = cache[i];
         match y {
             Some(val) => {
                 if val < y_start || val > y_end {
-                    if in_graph_area {
-                        rendering_context.line_to(x, val);
+                    if in_graph_area && joined {
+                        line_to(x, val);
                         in_graph_area = false;
                     }
                     else {
-                        let next_y = cache[i + 1].1;
+                        in_graph_area = false;
+                        let (_, next_y, next_joined) = cache[i + 1];
                         if let Some(next_val) = next_y {
-                            if next_val > y_start && next_val < y_end {
+                            if next_val > y_start && next_val < y_end && next_joined {
                                 in_graph_area = true;
-                                rendering_context.move_to(x, val);
+                                move_to(x, val);
                             }
                         }
                     }
                 }
                 else {
-                    if !in_graph_area {
+                    if !in_graph_area || !joined {
                         in_graph_area = true;
-                        rendering_context.move_to(x, val);
+                        move_to(x, val);
                     }
-                    else { rendering_context.line_to(x, val); }
+                    else { line_to(x, val); }
                 }
             },
             None => {
@@ -273,3 +512,318 @@ pub fn draw_function_graph_from_cache(rendering_context: &CanvasRenderingContext
     }
     rendering_context.stroke();
 }
+
+//function to draw the curve where the function represented by a Calculator struct is 0, within the current graph viewport - does not cache values
+pub fn draw_implicit_curve(rendering_context: &CanvasRenderingContext2d, calculator: &mut Calculator, x_start: f64, x_end: f64, y_start: f64, y_end: f64, idx: usize) {
+    rendering_context.set_stroke_style_str(COLORS[idx % COLORS.len()]);
+    let segments = implicit_curve_segments(|x, y| calculator.calculate_point(x, y), x_start, x_end, y_start, y_end);
+    rendering_context.begin_path();
+    for ((x0, y0), (x1, y1)) in segments {
+        rendering_context.move_to(x0, y0);
+        rendering_context.line_to(x1, y1);
+    }
+    rendering_context.stroke();
+}
+
+type Point = (f64, f64);
+
+/*function to draw the parametric curve (x(t), y(t)) for t from t_start to t_end, clipped to the current viewport - does not cache values.
+The curve is sampled adaptively (see parametric_points), so it stays smooth where it moves quickly*/
+#[allow(clippy::too_many_arguments)]
+pub fn draw_parametric_curve(rendering_context: &CanvasRenderingContext2d, x: &mut Calculator, y: &mut Calculator, t_start: f64, t_end: f64, x_start: f64, x_end: f64, y_start: f64, y_end: f64, idx: usize) {
+    rendering_context.set_stroke_style_str(COLORS[idx % COLORS.len()]);
+    let points = parametric_points(|t| Some((x.calculate(t)?, y.calculate(t)?)), t_start, t_end, x_start, x_end, y_start, y_end);
+    let in_graph_area = |(x, y): Point| x >= x_start && x <= x_end && y >= y_start && y <= y_end;
+    //point the last segment was drawn to, so that consecutive segments are drawn as one line
+    let mut pen = None;
+    rendering_context.begin_path();
+    //as in draw_function_graph_polar, a segment is only drawn if one of its ends is inside the viewport
+    for step in points.windows(2) {
+        if let (Some(p), Some(q)) = (step[0], step[1]) {
+            if in_graph_area(p) || in_graph_area(q) {
+                if pen != Some(p) { rendering_context.move_to(p.0, p.1); }
+                rendering_context.line_to(q.0, q.1);
+                pen = Some(q);
+            }
+        }
+    }
+    rendering_context.stroke();
+}
+
+/*function to draw a marker of the given shape at each point of a list, where points is the pair of Calculators for each point's 
+coordinates - points outside the current viewport, or with an undefined coordinate, aren't drawn. Markers are sized relative to the 
+viewport on each axis, so that they keep their shape on screen however the axes are scaled*/
+#[allow(clippy::too_many_arguments)]
+pub fn draw_points(rendering_context: &CanvasRenderingContext2d, points: &mut [(Calculator, Calculator)], x_start: f64, x_end: f64, y_start: f64, y_end: f64, idx: usize, shape: MarkerShape) {
+    rendering_context.set_stroke_style_str(COLORS[idx % COLORS.len()]);
+    rendering_context.set_fill_style_str(COLORS[idx % COLORS.len()]);
+    //coordinates of points don't use x, so any value can be passed
+    let points = points.iter_mut().map(|(x, y)| Some((x.calculate(0.0)?, y.calculate(0.0)?)));
+    let rx = (x_end - x_start) * MARKER_SIZE;
+    let ry = (y_end - y_start) * MARKER_SIZE;
+    rendering_context.begin_path();
+    for (x, y) in visible_points(points, x_start, x_end, y_start, y_end) {
+        match shape {
+            MarkerShape::Circle => {
+                rendering_context.move_to(x + rx, y);
+                rendering_context.ellipse(x, y, rx, ry, 0.0, 0.0, 2.0 * std::f64::consts::PI).unwrap();
+            }
+            MarkerShape::Square => rendering_context.rect(x - rx, y - ry, 2.0 * rx, 2.0 * ry),
+            MarkerShape::Triangle => {
+                rendering_context.move_to(x, y + ry);
+                rendering_context.line_to(x + rx, y - ry);
+                rendering_context.line_to(x - rx, y - ry);
+                rendering_context.close_path();
+            }
+            MarkerShape::Cross => {
+                rendering_context.move_to(x - rx, y - ry);
+                rendering_context.line_to(x + rx, y + ry);
+                rendering_context.move_to(x - rx, y + ry);
+                rendering_context.line_to(x + rx, y - ry);
+            }
+        }
+    }
+    //a cross is only lines, so is stroked rather than filled
+    if shape == MarkerShape::Cross { rendering_context.stroke(); }
+    else { rendering_context.fill(); }
+}
+
+//function to keep the points that are defined and inside the viewport, in order
+fn visible_points<I: Iterator<Item = Option<Point>>>(points: I, x_start: f64, x_end: f64, y_start: f64, y_end: f64) -> Vec<Point> {
+    points.flatten().filter(|(x, y)| *x >= x_start && *x <= x_end && *y >= y_start && *y <= y_end).collect()
+}
+
+/*function to sample the parametric curve f(t) for t from t_start to t_end - produces the points in order of t, with None wherever the curve 
+is broken. Each of PARAMETRIC_STEPS equal steps in t is halved until it is shorter than PARAMETRIC_MAX_STEP of the size of the viewport,
+unless both of its ends are past the same edge of the viewport, and a step that is still too long after being halved PARAMETRIC_MAX_DEPTH
+times jumps across a discontinuity, as in (t, tan(t)), so the curve is broken there*/
+fn parametric_points<F: FnMut(f64) -> Option<Point>>(mut f: F, t_start: f64, t_end: f64, x_start: f64, x_end: f64, y_start: f64, y_end: f64) -> Vec<Option<Point>> {
+    let viewport = (x_start, x_end, y_start, y_end);
+    let step = (t_end - t_start) / PARAMETRIC_STEPS as f64;
+    let mut previous = (t_start, f(t_start));
+    let mut points = vec![previous.1];
+    for i in 1..=PARAMETRIC_STEPS {
+        let t = t_start + step * i as f64;
+        let next = (t, f(t));
+        refine_parametric_step(&mut f, previous, next, viewport, 0, &mut points);
+        previous = next;
+    }
+    points
+}
+
+//function to add the points of the step of a parametric curve from (t0, p0) to (t1, p1) to points, halving it as needed - p0 is already in points
+fn refine_parametric_step<F: FnMut(f64) -> Option<Point>>(f: &mut F, (t0, p0): (f64, Option<Point>), (t1, p1): (f64, Option<Point>), viewport: (f64, f64, f64, f64), depth: usize, points: &mut Vec<Option<Point>>) {
+    let (x_start, x_end, y_start, y_end) = viewport;
+    let too_long = match (p0, p1) {
+        //steps that can't be seen are never refined
+        (Some(p), Some(q)) if (p.0 < x_start && q.0 < x_start) || (p.0 > x_end && q.0 > x_end) || (p.1 < y_start && q.1 < y_start)
+                              || (p.1 > y_end && q.1 > y_end) => false,
+        (Some(p), Some(q)) => ((q.0 - p.0) / (x_end - x_start)).hypot((q.1 - p.1) / (y_end - y_start)) > PARAMETRIC_MAX_STEP,
+        (None, None) => false,
+        //refine steps where the curve starts or stops being defined, so that it ends close to where it is last defined
+        _ => true
+    };
+    if too_long && depth < PARAMETRIC_MAX_DEPTH {
+        let t = (t0 + t1) / 2.0;
+        let mid = (t, f(t));
+        refine_parametric_step(f, (t0, p0), mid, viewport, depth + 1, points);
+        refine_parametric_step(f, mid, (t1, p1), viewport, depth + 1, points);
+        return;
+    }
+    if too_long && p0.is_some() && p1.is_some() { points.push(None); }
+    points.push(p1);
+}
+
+/*function to find line segments approximating the curve where f is 0 in the given viewport, using marching squares - f is first sampled
+on a coarse grid, then each coarse cell whose corners don't all have the same sign is divided into subcells, and the segments are found
+in each subcell. Where a segment meets a cell edge is refined by bisection, and sign changes that grow rather than shrink while being
+refined are discontinuities (as in y = tan(x)) rather than crossings, and are skipped*/
+fn implicit_curve_segments<F: FnMut(f64, f64) -> Option<f64>>(mut f: F, x_start: f64, x_end: f64, y_start: f64, y_end: f64) -> Vec<(Point, Point)> {
+    let n = IMPLICIT_GRID_SIZE;
+    let cell_width = (x_end - x_start) / n as f64;
+    let cell_height = (y_end - y_start) / n as f64;
+    //values of f at each coarse grid point, in rows of increasing y
+    let mut grid = Vec::with_capacity((n + 1) * (n + 1));
+    for j in 0..=n {
+        for i in 0..=n {
+            grid.push(f(x_start + i as f64 * cell_width, y_start + j as f64 * cell_height));
+        }
+    }
+    let mut segments = Vec::new();
+    for j in 0..n {
+        for i in 0..n {
+            let corners = [grid[j * (n + 1) + i], grid[j * (n + 1) + i + 1], grid[(j + 1) * (n + 1) + i + 1], grid[(j + 1) * (n + 1) + i]];
+            let signs: Vec<bool> = corners.iter().flatten().map(|value| *value >= 0.0).collect();
+            if signs.iter().all(|sign| *sign) || signs.iter().all(|sign| !*sign) { continue; }
+            //the curve passes through this cell, so find its segments on a finer grid
+            let x = x_start + i as f64 * cell_width;
+            let y = y_start + j as f64 * cell_height;
+            let subcell_width = cell_width / IMPLICIT_SUBDIVISIONS as f64;
+            let subcell_height = cell_height / IMPLICIT_SUBDIVISIONS as f64;
+            for sj in 0..IMPLICIT_SUBDIVISIONS {
+                for si in 0..IMPLICIT_SUBDIVISIONS {
+                    let x0 = x + si as f64 * subcell_width;
+                    let y0 = y + sj as f64 * subcell_height;
+                    cell_segments(&mut f, (x0, y0), (x0 + subcell_width, y0 + subcell_height), &mut segments);
+                }
+            }
+        }
+    }
+    segments
+}
+
+/*function to add the segments of the curve where f is 0 within the cell with the given bottom left and top right corners to segments - 
+skips cells where f is undefined at any corner*/
+fn cell_segments<F: FnMut(f64, f64) -> Option<f64>>(f: &mut F, (x0, y0): Point, (x1, y1): Point, segments: &mut Vec<(Point, Point)>) {
+    //corners counterclockwise from the bottom left
+    let points = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+    let mut values = [0.0; 4];
+    for (value, (x, y)) in values.iter_mut().zip(points) {
+        match f(x, y) {
+            Some(v) => *value = v,
+            None => return
+        }
+    }
+    //where the curve crosses each edge, with edge k running from corner k to corner k + 1, if it does
+    let mut crossings = [None; 4];
+    for (k, crossing) in crossings.iter_mut().enumerate() {
+        let next = (k + 1) % 4;
+        if (values[k] >= 0.0) != (values[next] >= 0.0) {
+            *crossing = Some(refine_crossing(f, points[k], points[next], values[k], values[next]));
+        }
+    }
+    let pairs = match crossings.iter().flatten().count() {
+        2 => {
+            let edges: Vec<usize> = (0..4).filter(|k| crossings[*k].is_some()).collect();
+            vec![(edges[0], edges[1])]
+        }
+        //a saddle, where opposite corners have the same sign - the sign at the center decides which corners the curve separates
+        4 => {
+            let center = f((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+            if center.is_some_and(|center| (center >= 0.0) == (values[0] >= 0.0)) { vec![(0, 1), (2, 3)] } else { vec![(3, 0), (1, 2)] }
+        }
+        _ => Vec::new()
+    };
+    segments.extend(pairs.into_iter().filter_map(|(a, b)| pair_crossings(&crossings, a, b)));
+}
+
+//function to produce the segment between the crossings on two edges, if neither is a discontinuity
+fn pair_crossings(crossings: &[Option<Option<Point>>; 4], a: usize, b: usize) -> Option<(Point, Point)> {
+    match (crossings[a], crossings[b]) {
+        (Some(Some(p)), Some(Some(q))) => Some((p, q)),
+        _ => None
+    }
+}
+
+/*function to find where f crosses 0 between points p and q, given f(p) and f(q) with opposite signs - narrows the interval by bisection,
+then interpolates linearly. Returns None if f is undefined along the way, or grows while narrowing, which means the sign change is a
+discontinuity*/
+fn refine_crossing<F: FnMut(f64, f64) -> Option<f64>>(f: &mut F, mut p: Point, mut q: Point, mut fp: f64, mut fq: f64) -> Option<Point> {
+    let initial = fp.abs().max(fq.abs());
+    for _ in 0..IMPLICIT_REFINEMENT_STEPS {
+        let mid = ((p.0 + q.0) / 2.0, (p.1 + q.1) / 2.0);
+        let fm = f(mid.0, mid.1)?;
+        if (fm >= 0.0) == (fp >= 0.0) {
+            p = mid;
+            fp = fm;
+        }
+        else {
+            q = mid;
+            fq = fm;
+        }
+    }
+    if fp.abs().max(fq.abs()) > initial { return None; }
+    let t = if fp == fq { 0.5 } else { fp / (fp - fq) };
+    Some((p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use crate::{calculator::generate_calculator, definition::resolve_parametric};
+
+    #[test]
+    fn test_visible_points() {
+        let points = vec![Some((0.0, 0.0)), Some((6.0, 1.0)), None, Some((-5.0, 5.0)), Some((1.0, -5.5)), Some((f64::NAN, 1.0))];
+        assert_eq!(visible_points(points.into_iter(), -5.0, 5.0, -5.0, 5.0), vec![(0.0, 0.0), (-5.0, 5.0)]);
+    }
+
+    #[test]
+    fn test_inequality_holds() {
+        let less = Inequality { side: Side::Less, inclusive: false };
+        let greater_equal = Inequality { side: Side::Greater, inclusive: true };
+        assert!(less.holds(1.0, 2.0));
+        assert!(!less.holds(2.0, 2.0));
+        assert!(greater_equal.holds(2.0, 2.0));
+        assert!(!greater_equal.holds(1.0, 2.0));
+    }
+
+    #[test]
+    fn test_implicit_circle() {
+        let segments = implicit_curve_segments(|x, y| Some(x * x + y * y - 25.0), -10.0, 10.0, -10.0, 10.0);
+        assert!(!segments.is_empty());
+        for (p, q) in segments {
+            for (x, y) in [p, q] {
+                assert!(((x * x + y * y).sqrt() - 5.0).abs() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_implicit_discontinuity() {
+        //y = tan(x) crosses 0 at its poles as well as on the curve, but only the curve should be drawn
+        let segments = implicit_curve_segments(|x, y| Some(y - x.tan()), -2.0, 2.0, -2.0, 2.0);
+        assert!(!segments.is_empty());
+        for (p, q) in segments {
+            for (x, y) in [p, q] {
+                assert!((y - x.tan()).abs() < 1e-2, "({}, {}) is not on the curve", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_joined() {
+        let joined = |expression: &str, start: f64, end: f64| {
+            let mut calculator = generate_calculator(resolve_parametric(&expression.replace('x', "t")).unwrap()).unwrap();
+            let (start_y, end_y) = (calculator.calculate(start), calculator.calculate(end));
+            is_joined(&mut calculator, (start, start_y), (end, end_y))
+        };
+        assert!(joined("x^2", 1.0, 1.01));
+        //a sign change or a steep jump between the samples is bounded to tell a break from a steep part of the graph
+        assert!(!joined("1/x", -0.01, 0.01));
+        assert!(!joined("floor(x)", 0.99, 1.01));
+        assert!(joined("100x", 0.0, 0.01));
+        assert!(!joined("sqrt(x)", -0.01, 0.01));
+    }
+
+    #[test]
+    fn test_parametric_circle() {
+        let points = parametric_points(|t| Some((3.0 * t.cos(), 3.0 * t.sin())), 0.0, 2.0 * PI, -5.0, 5.0, -5.0, 5.0);
+        assert!(points.iter().all(|point| point.is_some_and(|(x, y)| ((x * x + y * y).sqrt() - 3.0).abs() < 1e-9)));
+        //the circle is closed, and no step is longer than the maximum
+        let (first, last) = (points[0].unwrap(), points[points.len() - 1].unwrap());
+        assert!((first.0 - last.0).abs() < 1e-9 && (first.1 - last.1).abs() < 1e-9);
+        for step in points.windows(2) {
+            let (p, q) = (step[0].unwrap(), step[1].unwrap());
+            assert!((q.0 - p.0).hypot(q.1 - p.1) / 10.0 <= PARAMETRIC_MAX_STEP);
+        }
+    }
+
+    #[test]
+    fn test_parametric_discontinuity() {
+        //(t, tan(t)) jumps at t = pi/2, so the curve is broken there and nowhere else
+        let points = parametric_points(|t| Some((t, t.tan())), 1.0, 2.0, -5.0, 5.0, -5.0, 5.0);
+        assert_eq!(points.iter().filter(|point| point.is_none()).count(), 1);
+        let undefined = parametric_points(|t| if t < 0.5 { None } else { Some((t, t)) }, 0.0, 1.0, -5.0, 5.0, -5.0, 5.0);
+        let first = undefined.iter().find_map(|point| *point).unwrap();
+        assert!(first.0 >= 0.5 && first.0 - 0.5 < 1e-5);
+    }
+
+    #[test]
+    fn test_implicit_undefined() {
+        let segments = implicit_curve_segments(|x, y| if x < 0.0 { None } else { Some(x - y) }, -1.0, 1.0, -1.0, 1.0);
+        assert!(segments.iter().all(|(p, q)| p.0 >= 0.0 && q.0 >= 0.0));
+    }
+}
@@ -0,0 +1,229 @@
+/*
+STACK INTERPRETER:
+The evaluator Calculator used before expressions were compiled into slots (see calculator.rs) - each instruction pops its operands from a
+stack and pushes its result, and functions are looked up by name each time they are called. It is kept as a reference, which the compiled
+evaluator is tested against, and benchmarked against (see benches/calculator.rs), and isn't used to graph anything.
+*/
+
+use std::f64::consts::{PI, E};
+
+use crate::{calculator::{apply_function, compare, factorial, floored_mod, DEFAULT_PARAMETER_VALUE}, error::EvalError, scanner::{TokenType, RECOGNIZED_FUNCTIONS}, parser::{ASTNode, ASTNodeType}};
+
+pub struct StackCalculator {
+    instructions: Vec<StackInstruction>,
+    stack: Vec<f64>,
+    parameter_values: Vec<f64>
+}
+
+impl StackCalculator {
+    //function to calculate f(x) for the expression, with each parameter at its default value - returns None if f is undefined at x
+    pub fn calculate(&mut self, x: f64) -> Option<f64> {
+        self.stack.clear();
+        let mut pc = 0;
+        while pc < self.instructions.len() {
+            let instruction = &self.instructions[pc];
+            pc += 1;
+            match instruction {
+                StackInstruction::Push(val) => {
+                    match val {
+                        StackValue::Num(num) => self.stack.push(*num),
+                        StackValue::X => self.stack.push(x),
+                        StackValue::Parameter(i) => self.stack.push(self.parameter_values[*i])
+                    }
+                },
+                StackInstruction::Add => {
+                    let right = self.stack.pop().unwrap();
+                    let left = self.stack.pop().unwrap();
+                    self.stack.push(left + right);
+                },
+                StackInstruction::Sub => {
+                    let right = self.stack.pop().unwrap();
+                    let left = self.stack.pop().unwrap();
+                    self.stack.push(left - right);
+                },
+                StackInstruction::Mul => {
+                    let right = self.stack.pop().unwrap();
+                    let left = self.stack.pop().unwrap();
+                    self.stack.push(left * right);
+                },
+                StackInstruction::Div => {
+                    let right = self.stack.pop().unwrap();
+                    let left = self.stack.pop().unwrap();
+                    if right == 0.0 { return None; }
+                    self.stack.push(left / right);
+                },
+                StackInstruction::Exp => {
+                    let power = self.stack.pop().unwrap();
+                    let base = self.stack.pop().unwrap();
+                    let val = base.powf(power);
+                    if val.is_nan() { return None; }
+                    self.stack.push(val);
+                },
+                StackInstruction::Mod => {
+                    let right = self.stack.pop().unwrap();
+                    let left = self.stack.pop().unwrap();
+                    self.stack.push(floored_mod(left, right)?);
+                },
+                StackInstruction::Neg => {
+                    let arg = self.stack.pop().unwrap();
+                    self.stack.push(-arg);
+                },
+                StackInstruction::Factorial => {
+                    let arg = self.stack.pop().unwrap();
+                    self.stack.push(factorial(arg)?);
+                },
+                StackInstruction::ApplyFunc(f, arg_count) => {
+                    let args = self.stack.split_off(self.stack.len() - arg_count);
+                    if let TokenType::FunctionName(function_name) = f {
+                        self.stack.push(apply_function(function_name, &args)?);
+                    }
+                },
+                StackInstruction::Compare(operator) => {
+                    let right = self.stack.pop().unwrap();
+                    let left = self.stack.pop().unwrap();
+                    self.stack.push(compare(operator, left, right));
+                },
+                StackInstruction::Jump(skip) => pc += skip,
+                StackInstruction::JumpUnless(skip) => {
+                    if self.stack.pop().unwrap() == 0.0 { pc += skip; }
+                },
+                StackInstruction::Undefined => return None
+            }
+        }
+        Some(self.stack.pop().unwrap())
+    }
+}
+
+//function to create a stack calculator for an AST, as it is given - y is calculated at y = x, as in Calculator::calculate
+pub fn generate_stack_calculator(expression: ASTNode) -> Result<StackCalculator, EvalError> {
+    let mut parameters = Vec::new();
+    let instructions = generate_instructions(expression, &mut parameters)?;
+    Ok(StackCalculator { instructions, stack: Vec::new(), parameter_values: vec![DEFAULT_PARAMETER_VALUE; parameters.len()] })
+}
+
+//function to take an AST node and produce a vector of instructions for the stack calculator to run
+fn generate_instructions(expression: ASTNode, parameters: &mut Vec<String>) -> Result<Vec<StackInstruction>, EvalError> {
+    let mut instructions : Vec<StackInstruction> = Vec::new();
+    let span = expression.span;
+    match expression.node_type {
+        ASTNodeType::BinaryExpression(operator,left , right) => {
+            instructions.append(&mut generate_instructions(*left, parameters)?);
+            instructions.append(&mut generate_instructions(*right, parameters)?);
+            match operator {
+                TokenType::Add => instructions.push(StackInstruction::Add),
+                TokenType::Sub => instructions.push(StackInstruction::Sub),
+                TokenType::Mul => instructions.push(StackInstruction::Mul),
+                TokenType::Div => instructions.push(StackInstruction::Div),
+                TokenType::Exp => instructions.push(StackInstruction::Exp),
+                TokenType::Mod => instructions.push(StackInstruction::Mod),
+                other => return Err(EvalError::UnsupportedOperator { operator: other.to_string(), span })
+            };
+        }
+        ASTNodeType::UnaryExpression(operator,expression ) => {
+            instructions.append(&mut generate_instructions(*expression, parameters)?);
+            match operator {
+                TokenType::Sub => instructions.push(StackInstruction::Neg),
+                TokenType::Factorial => instructions.push(StackInstruction::Factorial),
+                other => return Err(EvalError::UnsupportedOperator { operator: other.to_string(), span })
+            }
+        }
+        ASTNodeType::FunctionExpression(function_name, arguments) => {
+            if !RECOGNIZED_FUNCTIONS.contains(&function_name.to_string().as_str()) {
+                return Err(EvalError::UnknownFunction { name: function_name.to_string(), span });
+            }
+            let arg_count = arguments.len();
+            for argument in arguments {
+                instructions.append(&mut generate_instructions(argument, parameters)?);
+            }
+            instructions.push(StackInstruction::ApplyFunc(function_name, arg_count));
+        }
+        ASTNodeType::InequalityExpression(operator, left, right) => {
+            instructions.append(&mut generate_instructions(*left, parameters)?);
+            instructions.append(&mut generate_instructions(*right, parameters)?);
+            instructions.push(StackInstruction::Compare(operator));
+        }
+        ASTNodeType::LogicalExpression(operator, left, right) => {
+            let mut right = generate_instructions(*right, parameters)?;
+            instructions.append(&mut generate_instructions(*left, parameters)?);
+            if operator == TokenType::And {
+                instructions.push(StackInstruction::JumpUnless(right.len() + 1));
+                instructions.append(&mut right);
+                instructions.push(StackInstruction::Jump(1));
+                instructions.push(StackInstruction::Push(StackValue::Num(0.0)));
+            }
+            else {
+                instructions.push(StackInstruction::JumpUnless(2));
+                instructions.push(StackInstruction::Push(StackValue::Num(1.0)));
+                instructions.push(StackInstruction::Jump(right.len()));
+                instructions.append(&mut right);
+            }
+        }
+        ASTNodeType::PiecewiseExpression(branches, default) => {
+            let mut remaining = match default {
+                Some(default) => generate_instructions(*default, parameters)?,
+                None => vec![StackInstruction::Undefined]
+            };
+            let mut generated = Vec::new();
+            for (condition, value) in branches {
+                generated.push((generate_instructions(condition, parameters)?, generate_instructions(value, parameters)?));
+            }
+            for (mut condition, mut value) in generated.into_iter().rev() {
+                condition.push(StackInstruction::JumpUnless(value.len() + 1));
+                condition.append(&mut value);
+                condition.push(StackInstruction::Jump(remaining.len()));
+                condition.append(&mut remaining);
+                remaining = condition;
+            }
+            instructions.append(&mut remaining);
+        }
+        ASTNodeType::PointExpression(_, _) | ASTNodeType::ListExpression(_) => return Err(EvalError::UnexpectedPoint { span }),
+        ASTNodeType::AtomicExpression(expression) => {
+            match expression {
+                TokenType::NumLiteral(num) => instructions.push(StackInstruction::Push(StackValue::Num(num))),
+                TokenType::Variable(_) => instructions.push(StackInstruction::Push(StackValue::X)),
+                TokenType::Parameter(name) => {
+                    let i = match parameters.iter().position(|parameter| *parameter == name) {
+                        Some(i) => i,
+                        None => {
+                            parameters.push(name);
+                            parameters.len() - 1
+                        }
+                    };
+                    instructions.push(StackInstruction::Push(StackValue::Parameter(i)));
+                }
+                TokenType::Constant(c) => {
+                    match c.as_str() {
+                        "pi" => instructions.push(StackInstruction::Push(StackValue::Num(PI))),
+                        "e" => instructions.push(StackInstruction::Push(StackValue::Num(E))),
+                        _ => return Err(EvalError::UnknownConstant { name: c, span })
+                    }
+                }
+                other => return Err(EvalError::UnsupportedOperator { operator: other.to_string(), span })
+            }
+        }
+    }
+    Ok(instructions)
+}
+
+enum StackInstruction {
+    Push(StackValue),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Exp,
+    Mod,
+    Neg,
+    Factorial,
+    ApplyFunc(TokenType, usize),
+    Compare(TokenType),
+    Jump(usize),
+    JumpUnless(usize),
+    Undefined
+}
+
+enum StackValue {
+    X,
+    Parameter(usize),
+    Num(f64)
+}
@@ -0,0 +1,390 @@
+/*
+INTERVAL ARITHMETIC:
+Each operation here takes bounds on the values of its operands over a range of x, and produces bounds on its result over that range, so
+that evaluating an expression this way (see calculate_interval in calculator.rs) bounds every value it takes there, up to rounding. As
+well as the range of values, Bounds records whether the expression is defined everywhere in the range, and whether it is continuous
+there, so that the graph of a function can be drawn as one unbroken line across it. An operation that can't tell gives up safely -
+values that are unbounded, a pole or a jump that may be in the range, and a condition that holds in only part of the range all make
+the result discontinuous. An expression that is undefined everywhere in the range has no bounds at all (None).
+*/
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::{calculator::{factorial, Function}, scanner::TokenType};
+
+//where x! has its minimum for x > -1, and the minimum
+const FACTORIAL_MIN_X: f64 = 0.461_632_144_968_362_3;
+const FACTORIAL_MIN: f64 = 0.885_603_194_410_888_7;
+
+//bounds on the values of an expression over a range
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Bounds {
+    pub lo: f64,
+    pub hi: f64,
+    //whether the expression is defined everywhere in the range
+    pub defined: bool,
+    //whether the expression is defined and continuous everywhere in the range
+    pub continuous: bool
+}
+
+impl Bounds {
+    /*function to create bounds from lo to hi that are defined and continuous - a NaN bound is widened to infinity, and unbounded values
+    can't be graphed, so aren't defined*/
+    pub fn new(lo: f64, hi: f64) -> Bounds {
+        let lo = if lo.is_nan() { f64::NEG_INFINITY } else { lo };
+        let hi = if hi.is_nan() { f64::INFINITY } else { hi };
+        let bounded = lo.is_finite() && hi.is_finite();
+        Bounds { lo, hi, defined: bounded, continuous: bounded }
+    }
+
+    pub fn point(value: f64) -> Bounds {
+        Bounds::new(value, value)
+    }
+
+    //bounds that hold for any value
+    fn unbounded() -> Bounds {
+        Bounds::new(f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    pub fn is_point(&self) -> bool {
+        self.lo == self.hi
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    //function to take on what the bounds of an operand say about where it is defined and continuous
+    pub fn inherit(mut self, operand: Bounds) -> Bounds {
+        self.defined &= operand.defined;
+        self.continuous &= operand.continuous;
+        self
+    }
+
+    //function to bound the values of either of two alternatives
+    pub fn union(self, other: Bounds) -> Bounds {
+        Bounds::new(self.lo.min(other.lo), self.hi.max(other.hi)).inherit(self).inherit(other)
+    }
+
+    //function to mark the bounds as possibly jumping somewhere in the range
+    fn broken(mut self) -> Bounds {
+        self.continuous = false;
+        self
+    }
+
+    //function to mark the bounds as undefined somewhere in the range
+    pub fn partial(mut self) -> Bounds {
+        self.defined = false;
+        self.continuous = false;
+        self
+    }
+}
+
+//function to bound the smallest and largest of some values, with the flags of the operands they were calculated from
+fn spread(values: &[f64], operands: &[Bounds]) -> Bounds {
+    let lo = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    operands.iter().fold(Bounds::new(lo, hi), |bounds, operand| bounds.inherit(*operand))
+}
+
+pub fn add(a: Bounds, b: Bounds) -> Bounds {
+    spread(&[a.lo + b.lo, a.hi + b.hi], &[a, b])
+}
+
+pub fn sub(a: Bounds, b: Bounds) -> Bounds {
+    spread(&[a.lo - b.hi, a.hi - b.lo], &[a, b])
+}
+
+pub fn mul(a: Bounds, b: Bounds) -> Bounds {
+    //0 times an infinite bound is 0, since the bound is never reached
+    let product = |x: f64, y: f64| if x == 0.0 || y == 0.0 { 0.0 } else { x * y };
+    spread(&[product(a.lo, b.lo), product(a.lo, b.hi), product(a.hi, b.lo), product(a.hi, b.hi)], &[a, b])
+}
+
+//function to bound a / b - b may be 0 somewhere in the range, where the quotient is undefined and may have a pole
+pub fn div(a: Bounds, b: Bounds) -> Option<Bounds> {
+    if b.lo == 0.0 && b.hi == 0.0 { return None; }
+    if !b.contains(0.0) {
+        return Some(mul(a, spread(&[1.0 / b.lo, 1.0 / b.hi], &[b])));
+    }
+    let reciprocal = if b.lo == 0.0 { Bounds::new(1.0 / b.hi, f64::INFINITY) }
+                     else if b.hi == 0.0 { Bounds::new(f64::NEG_INFINITY, 1.0 / b.lo) }
+                     else { Bounds::unbounded() };
+    Some(mul(a, reciprocal).partial())
+}
+
+/*function to bound base ^ power - a negative base only has a power where the power is an integer, and a power of 0 is undefined where
+the power is negative*/
+pub fn pow(base: Bounds, power: Bounds) -> Option<Bounds> {
+    let corners = |base: Bounds| spread(&[base.lo.powf(power.lo), base.lo.powf(power.hi), base.hi.powf(power.lo), base.hi.powf(power.hi)],
+                                        &[base, power]);
+    //powers of a positive base are monotonic in both the base and the power, so are bounded by the corners
+    if base.lo > 0.0 { return Some(corners(base)); }
+    if power.is_point() && power.lo.fract() == 0.0 {
+        let n = power.lo;
+        if n < 0.0 { return div(Bounds::point(1.0), pow(base, Bounds::point(-n))?); }
+        //even powers have their minimum at 0
+        let mut bounds = spread(&[base.lo.powf(n), base.hi.powf(n)], &[base]);
+        if n % 2.0 == 0.0 && base.contains(0.0) { bounds.lo = 0.0; }
+        return Some(bounds);
+    }
+    //a negative base has powers that can't be bounded wherever the power may be an integer, and none elsewhere
+    if base.lo < 0.0 && power.lo.ceil() <= power.hi { return Some(Bounds::unbounded()); }
+    if base.hi < 0.0 { return None; }
+    let defined = base.lo >= 0.0;
+    let bounds = corners(Bounds { lo: 0.0, ..base });
+    //0 to a power that may be 0 or less is either undefined or jumps
+    let bounds = if power.lo <= 0.0 && !(power.is_point() && power.lo == 0.0) { bounds.partial() } else { bounds };
+    Some(if defined { bounds } else { bounds.partial() })
+}
+
+//function to bound x mod m, taking the sign of m as in floored_mod - undefined where m is 0, and jumps wherever x crosses a multiple of m
+pub fn modulo(x: Bounds, m: Bounds) -> Option<Bounds> {
+    if m.lo == 0.0 && m.hi == 0.0 { return None; }
+    //the result is between 0 and m
+    let any = spread(&[0.0, m.lo, m.hi], &[x, m]);
+    if m.contains(0.0) { return Some(any.partial()); }
+    if !m.is_point() { return Some(any.broken()); }
+    let (lo, hi) = ((x.lo / m.lo).floor(), (x.hi / m.lo).floor());
+    if lo != hi || !lo.is_finite() { return Some(any.broken()); }
+    //x stays between the same multiples of m, so x mod m is x shifted by one of them
+    Some(spread(&[x.lo - m.lo * lo, x.hi - m.lo * lo], &[x, m]))
+}
+
+//function to bound x! - poles at the negative integers, and |x!| is log-convex between them, so is largest at the ends of the range
+pub fn factorial_bounds(x: Bounds) -> Option<Bounds> {
+    let value = |x: f64| factorial(x).unwrap_or(f64::INFINITY);
+    //the largest pole that may be in the range
+    let pole = x.hi.floor().min(-1.0);
+    if pole >= x.lo {
+        if x.is_point() { return None; }
+        return Some(Bounds::unbounded());
+    }
+    let (at_lo, at_hi) = (value(x.lo), value(x.hi));
+    let bounds = if x.lo >= FACTORIAL_MIN_X { Bounds::new(at_lo, at_hi) }
+                 else if x.lo > -1.0 && x.hi <= FACTORIAL_MIN_X { Bounds::new(at_hi, at_lo) }
+                 else if x.lo > -1.0 { Bounds::new(FACTORIAL_MIN, at_lo.max(at_hi)) }
+                 //between two poles below -1, x! keeps one sign
+                 else if at_lo > 0.0 { Bounds::new(0.0, at_lo.max(at_hi)) }
+                 else { Bounds::new(at_lo.min(at_hi), 0.0) };
+    Some(bounds.inherit(x))
+}
+
+//function to bound a comparison - 1 where it holds and 0 where it doesn't, so it jumps if it holds in only part of the range
+pub fn compare(operator: &TokenType, a: Bounds, b: Bounds) -> Bounds {
+    let (holds, fails) = match operator {
+        TokenType::Less => (a.hi < b.lo, a.lo >= b.hi),
+        TokenType::LessEqual => (a.hi <= b.lo, a.lo > b.hi),
+        TokenType::Greater => (a.lo > b.hi, a.hi <= b.lo),
+        _ => (a.lo >= b.hi, a.hi < b.lo)
+    };
+    let bounds = if holds { Bounds::point(1.0) } else if fails { Bounds::point(0.0) } else { Bounds::new(0.0, 1.0).broken() };
+    bounds.inherit(a).inherit(b)
+}
+
+//functions to bound a function that is increasing or decreasing everywhere in its domain
+fn increasing(x: Bounds, f: fn(f64) -> f64) -> Bounds {
+    Bounds::new(f(x.lo), f(x.hi)).inherit(x)
+}
+
+fn decreasing(x: Bounds, f: fn(f64) -> f64) -> Bounds {
+    Bounds::new(f(x.hi), f(x.lo)).inherit(x)
+}
+
+//function to restrict x to the domain from lo to hi - None if x is outside it everywhere, and undefined if x is outside it anywhere
+fn restrict(x: Bounds, lo: f64, hi: f64) -> Option<Bounds> {
+    if x.hi < lo || x.lo > hi { return None; }
+    let restricted = Bounds { lo: x.lo.max(lo), hi: x.hi.min(hi), ..x };
+    Some(if x.lo < lo || x.hi > hi { restricted.partial() } else { restricted })
+}
+
+//function to check whether the range of x includes offset plus a multiple of period
+fn includes_multiple(x: Bounds, offset: f64, period: f64) -> bool {
+    offset + ((x.lo - offset) / period).ceil() * period <= x.hi
+}
+
+//function to bound a step function, which jumps wherever its value changes
+fn step(x: Bounds, f: fn(f64) -> f64) -> Bounds {
+    let bounds = increasing(x, f);
+    if bounds.is_point() { bounds } else { bounds.broken() }
+}
+
+fn sin(x: Bounds) -> Bounds {
+    if !(x.hi - x.lo).is_finite() || x.hi - x.lo >= 2.0 * PI { return Bounds::new(-1.0, 1.0).inherit(x); }
+    let mut bounds = spread(&[x.lo.sin(), x.hi.sin()], &[x]);
+    if includes_multiple(x, FRAC_PI_2, 2.0 * PI) { bounds.hi = 1.0; }
+    if includes_multiple(x, -FRAC_PI_2, 2.0 * PI) { bounds.lo = -1.0; }
+    bounds
+}
+
+fn cos(x: Bounds) -> Bounds {
+    if !(x.hi - x.lo).is_finite() || x.hi - x.lo >= 2.0 * PI { return Bounds::new(-1.0, 1.0).inherit(x); }
+    let mut bounds = spread(&[x.lo.cos(), x.hi.cos()], &[x]);
+    if includes_multiple(x, 0.0, 2.0 * PI) { bounds.hi = 1.0; }
+    if includes_multiple(x, PI, 2.0 * PI) { bounds.lo = -1.0; }
+    bounds
+}
+
+//function to bound a function with a pole at offset plus each multiple of π, that is monotonic between its poles
+fn periodic_poles(x: Bounds, offset: f64, f: fn(f64) -> f64, increasing_between: bool) -> Option<Bounds> {
+    if includes_multiple(x, offset, PI) || !(x.hi - x.lo).is_finite() || x.hi - x.lo >= PI {
+        if x.is_point() { return None; }
+        return Some(Bounds::unbounded());
+    }
+    Some(if increasing_between { increasing(x, f) } else { decreasing(x, f) })
+}
+
+fn ln(x: Bounds) -> Option<Bounds> {
+    if x.hi <= 0.0 { return None; }
+    Some(increasing(restrict(x, 0.0, f64::INFINITY)?, f64::ln))
+}
+
+//function to bound the nth root of x for a constant n - as in apply_function, negative x only has a root when n is an odd integer
+fn root(n: f64, x: Bounds) -> Option<Bounds> {
+    if n == 0.0 { return None; }
+    if n < 0.0 { return div(Bounds::point(1.0), root(-n, x)?); }
+    //odd roots are increasing everywhere
+    if n.fract() == 0.0 && n % 2.0 != 0.0 {
+        let odd_root = |v: f64| if v < 0.0 { -(-v).powf(1.0 / n) } else { v.powf(1.0 / n) };
+        return Some(Bounds::new(odd_root(x.lo), odd_root(x.hi)).inherit(x));
+    }
+    if x.hi < 0.0 { return None; }
+    let x = restrict(x, 0.0, f64::INFINITY)?;
+    Some(Bounds::new(x.lo.powf(1.0 / n), x.hi.powf(1.0 / n)).inherit(x))
+}
+
+/*function to bound a built in function over the bounds of its arguments, as Function::apply in calculator.rs calculates it at a point - None
+where the function is undefined everywhere*/
+pub fn apply_function(function: Function, args: &[Bounds]) -> Option<Bounds> {
+    let x = args[0];
+    let bounds = match function {
+        Function::Sin => sin(x),
+        Function::Cos => cos(x),
+        Function::Tan => periodic_poles(x, FRAC_PI_2, f64::tan, true)?,
+        Function::Sec => div(Bounds::point(1.0), cos(x))?,
+        Function::Csc => div(Bounds::point(1.0), sin(x))?,
+        Function::Cot => periodic_poles(x, 0.0, |x| x.cos() / x.sin(), false)?,
+        Function::Asin => increasing(restrict(x, -1.0, 1.0)?, f64::asin),
+        Function::Acos => decreasing(restrict(x, -1.0, 1.0)?, f64::acos),
+        Function::Atan => increasing(x, f64::atan),
+        Function::Sinh => increasing(x, f64::sinh),
+        Function::Cosh => {
+            let mut bounds = spread(&[x.lo.cosh(), x.hi.cosh()], &[x]);
+            if x.contains(0.0) { bounds.lo = 1.0; }
+            bounds
+        },
+        Function::Tanh => increasing(x, f64::tanh),
+        Function::Asinh => increasing(x, f64::asinh),
+        Function::Acosh => increasing(restrict(x, 1.0, f64::INFINITY)?, f64::acosh),
+        Function::Atanh => {
+            if x.hi <= -1.0 || x.lo >= 1.0 { return None; }
+            increasing(restrict(x, -1.0, 1.0)?, f64::atanh)
+        },
+        Function::Log if args.len() == 2 => div(ln(args[1])?, ln(x)?)?,
+        Function::Log => {
+            if x.hi <= 0.0 { return None; }
+            increasing(restrict(x, 0.0, f64::INFINITY)?, f64::log10)
+        },
+        Function::Ln => ln(x)?,
+        Function::Exp => increasing(x, f64::exp),
+        Function::Sqrt => {
+            if x.hi < 0.0 { return None; }
+            increasing(restrict(x, 0.0, f64::INFINITY)?, f64::sqrt)
+        },
+        Function::Cbrt => increasing(x, f64::cbrt),
+        Function::Abs => {
+            if x.lo >= 0.0 { x }
+            else if x.hi <= 0.0 { decreasing(x, f64::abs) }
+            else { spread(&[0.0, -x.lo, x.hi], &[x]) }
+        },
+        Function::Floor => step(x, f64::floor),
+        Function::Ceil => step(x, f64::ceil),
+        Function::Round => step(x, f64::round),
+        Function::Sign => step(x, |x| if x == 0.0 { 0.0 } else { x.signum() }),
+        Function::Max => spread(&[args.iter().map(|arg| arg.lo).fold(f64::NEG_INFINITY, f64::max), args.iter().map(|arg| arg.hi).fold(f64::NEG_INFINITY, f64::max)], args),
+        Function::Min => spread(&[args.iter().map(|arg| arg.lo).fold(f64::INFINITY, f64::min), args.iter().map(|arg| arg.hi).fold(f64::INFINITY, f64::min)], args),
+        Function::Atan2 => {
+            let (y, x) = (args[0], args[1]);
+            //the angle jumps across the negative x-axis, and is undefined in direction at the origin, but elsewhere is bounded by the corners
+            if y.contains(0.0) && x.lo <= 0.0 {
+                spread(&[-PI, PI], args).broken()
+            }
+            else { spread(&[y.lo.atan2(x.lo), y.lo.atan2(x.hi), y.hi.atan2(x.lo), y.hi.atan2(x.hi)], args) }
+        },
+        Function::Root => {
+            let (n, x) = (args[0], args[1]);
+            if n.is_point() { root(n.lo, x)?.inherit(n) }
+            else if x.lo > 0.0 && !n.contains(0.0) { pow(x, div(Bounds::point(1.0), n)?)? }
+            else { Bounds::unbounded() }
+        },
+        Function::Mod => modulo(x, args[1])?
+    };
+    Some(bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(lo: f64, hi: f64) -> Bounds {
+        Bounds::new(lo, hi)
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(add(bounds(1.0, 2.0), bounds(-3.0, 1.0)), bounds(-2.0, 3.0));
+        assert_eq!(sub(bounds(1.0, 2.0), bounds(-3.0, 1.0)), bounds(0.0, 5.0));
+        assert_eq!(mul(bounds(-1.0, 2.0), bounds(-3.0, 1.0)), bounds(-6.0, 3.0));
+        assert_eq!(div(bounds(1.0, 2.0), bounds(2.0, 4.0)), Some(bounds(0.25, 1.0)));
+        assert_eq!(div(bounds(1.0, 2.0), bounds(0.0, 0.0)), None);
+        //a divisor that may be 0 gives a pole
+        let pole = div(bounds(1.0, 2.0), bounds(-1.0, 1.0)).unwrap();
+        assert!(!pole.defined && !pole.continuous && pole.lo == f64::NEG_INFINITY);
+        assert_eq!(div(bounds(0.0, 0.0), bounds(-1.0, 1.0)).unwrap().hi, 0.0);
+    }
+
+    #[test]
+    fn test_powers() {
+        assert_eq!(pow(bounds(-2.0, 3.0), Bounds::point(2.0)), Some(bounds(0.0, 9.0)));
+        assert_eq!(pow(bounds(-2.0, 3.0), Bounds::point(3.0)), Some(bounds(-8.0, 27.0)));
+        assert_eq!(pow(bounds(-3.0, -1.0), Bounds::point(0.5)), None);
+        assert!(!pow(bounds(-1.0, 4.0), Bounds::point(0.5)).unwrap().defined);
+        assert!(!pow(bounds(-1.0, 1.0), Bounds::point(-1.0)).unwrap().continuous);
+        assert_eq!(pow(bounds(1.0, 2.0), bounds(1.0, 2.0)), Some(bounds(1.0, 4.0)));
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(apply_function(Function::Sin, &[bounds(0.0, PI)]).unwrap(), bounds(0.0, 1.0));
+        assert_eq!(apply_function(Function::Cos, &[bounds(-10.0, 10.0)]).unwrap(), bounds(-1.0, 1.0));
+        assert!(!apply_function(Function::Tan, &[bounds(1.5, 1.6)]).unwrap().continuous);
+        assert!(apply_function(Function::Tan, &[bounds(1.4, 1.5)]).unwrap().continuous);
+        assert!(!apply_function(Function::Sec, &[bounds(1.5, 1.6)]).unwrap().continuous);
+        assert_eq!(apply_function(Function::Sqrt, &[bounds(-4.0, -1.0)]), None);
+        assert_eq!(apply_function(Function::Sqrt, &[bounds(-4.0, 4.0)]).map(|b| (b.lo, b.hi, b.defined)), Some((0.0, 2.0, false)));
+        assert_eq!(apply_function(Function::Ln, &[bounds(-1.0, 0.0)]), None);
+        assert!(!apply_function(Function::Floor, &[bounds(0.5, 1.5)]).unwrap().continuous);
+        assert_eq!(apply_function(Function::Floor, &[bounds(0.5, 0.9)]).unwrap(), Bounds::point(0.0));
+        assert_eq!(apply_function(Function::Max, &[bounds(0.0, 1.0), bounds(-1.0, 2.0)]).unwrap(), bounds(0.0, 2.0));
+        assert!(!apply_function(Function::Atan2, &[bounds(-1.0, 1.0), bounds(-2.0, -1.0)]).unwrap().continuous);
+        assert_eq!(apply_function(Function::Root, &[Bounds::point(3.0), bounds(-8.0, 27.0)]).unwrap(), bounds(-2.0, 3.0));
+    }
+
+    #[test]
+    fn test_modulo_and_factorial() {
+        assert_eq!(modulo(bounds(4.0, 5.0), Bounds::point(3.0)), Some(bounds(1.0, 2.0)));
+        assert_eq!(modulo(bounds(2.0, 4.0), Bounds::point(3.0)).map(|b| (b.lo, b.hi, b.continuous)), Some((0.0, 3.0, false)));
+        assert_eq!(factorial_bounds(bounds(2.0, 3.0)), Some(bounds(2.0, 6.0)));
+        assert!(!factorial_bounds(bounds(-1.5, -0.5)).unwrap().continuous);
+        assert_eq!(factorial_bounds(Bounds::point(-2.0)), None);
+        let between = factorial_bounds(bounds(-0.5, 1.0)).unwrap();
+        assert!(between.lo == FACTORIAL_MIN && (between.hi - PI.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare(&TokenType::Less, bounds(0.0, 1.0), bounds(2.0, 3.0)), Bounds::point(1.0));
+        assert_eq!(compare(&TokenType::GreaterEqual, bounds(0.0, 1.0), bounds(2.0, 3.0)), Bounds::point(0.0));
+        assert!(!compare(&TokenType::Less, bounds(0.0, 2.5), bounds(2.0, 3.0)).continuous);
+    }
+}
@@ -1,53 +1,177 @@
-use calculator::{generate_calculator, Calculator};
-use parser::parse;
-use scanner::scan;
+use calculator::{generate_calculator, generate_point_calculators, Calculator, DEFAULT_PARAMETER_VALUE};
+use derivative::derivatives;
+use definition::{default_theta_range, resolve_definitions, resolve_parametric, ResolvedInput};
+use error::{Diagnostic, ExpressionError, ParametricDiagnostic};
 use graph::*;
+use serde::Deserialize;
 use wasm_bindgen::prelude::*;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 use std::cell::RefCell;
 
+mod error;
 mod scanner;
 mod parser;
 mod calculator;
+mod interval;
+mod dual;
+mod derivative;
+mod simplify;
+mod definition;
 mod graph;
+#[cfg(any(test, feature = "bench"))]
+mod interpreter;
+
+/*entry points for the benchmarks in benches/, which compare the compiled evaluator with the stack interpreter - not part of the JS API, and
+only built with the bench feature*/
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::{calculator::generate_calculator, interpreter::generate_stack_calculator, parser::parse, scanner::scan, simplify::optimize};
+}
+
+//points calculated for a function, in order of increasing x
+type PointCache = Vec<CachedPoint>;
+
+//coordinates a function is graphed in - y = f(x) in cartesian coordinates, and r = f(θ) in polar coordinates
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum CoordinateMode {
+    #[default]
+    Cartesian,
+    Polar
+}
+
+//settings set from JS for an input
+#[derive(Debug, Clone, Copy, Default)]
+struct InputSettings {
+    mode: CoordinateMode,
+    //range of θ the function is graphed over in polar mode, None for its default range
+    theta_range: Option<(f64, f64)>,
+    //shape of the markers drawn for a list of points
+    marker: MarkerShape,
+    //whether the first and second derivatives of a function are drawn under it
+    show_derivatives: bool
+}
+
+//what is calculated to graph an input - a function, or the coordinates of each of a list of points
+enum Graph {
+    Function(Calculator),
+    Points(Vec<(Calculator, Calculator)>)
+}
+
+//a function or list of points to graph, along with how it is plotted
+struct FunctionEntry {
+    graph: Graph,
+    kind: PlotKind,
+    settings: InputSettings,
+    //first and second derivatives of a function, drawn when its settings show them - empty for anything else, or where it can't be differentiated
+    derivatives: Vec<Calculator>,
+    //range of θ the function is graphed over in polar mode, unless another range is set for it from JS
+    default_theta_range: (f64, f64)
+}
+
+impl FunctionEntry {
+    //function to check whether the entry is graphed in polar coordinates - only functions of x are, whatever its mode
+    fn is_polar(&self) -> bool {
+        self.settings.mode == CoordinateMode::Polar && self.kind == PlotKind::Function(Axis::X)
+    }
+
+    //function to get the range of θ the function is graphed over in polar mode
+    fn theta_range(&self) -> (f64, f64) {
+        self.settings.theta_range.unwrap_or(self.default_theta_range)
+    }
+
+    //function to get each Calculator used to graph the entry, including those of its derivatives
+    fn calculators(&mut self) -> Vec<&mut Calculator> {
+        let mut calculators: Vec<&mut Calculator> = match &mut self.graph {
+            Graph::Function(calculator) => vec![calculator],
+            Graph::Points(points) => points.iter_mut().flat_map(|(x, y)| [x, y]).collect()
+        };
+        calculators.extend(self.derivatives.iter_mut());
+        calculators
+    }
+
+    //function to get the parameters used by any of the entry's Calculators, in order of first use
+    fn parameters(&mut self) -> Vec<String> {
+        let mut parameters: Vec<String> = Vec::new();
+        for calculator in self.calculators() {
+            for parameter in calculator.parameters() {
+                if !parameters.contains(parameter) { parameters.push(parameter.clone()); }
+            }
+        }
+        parameters
+    }
+}
+
+//a parametric curve passed from JS - x and y are expressions of t, and the curve is graphed for t from t_start to t_end
+#[derive(Deserialize)]
+struct ParametricInput {
+    x: String,
+    y: String,
+    t_start: f64,
+    t_end: f64
+}
+
+//a parametric curve, with a Calculator for each coordinate
+struct ParametricCurve {
+    x: Calculator,
+    y: Calculator,
+    t_start: f64,
+    t_end: f64
+}
 
 //struct to represent global state
 struct AppState {
-    calculators: RefCell<Vec<Calculator>>,
+    /*one entry per expression passed to initialize, None for empty or invalid expressions - keeps each function's index (and color) equal
+    to its input's*/
+    calculators: RefCell<Vec<Option<FunctionEntry>>>,
+    //one entry per curve passed to initialize_parametric, None for empty or invalid curves - colored after the functions
+    parametric_curves: RefCell<Vec<Option<ParametricCurve>>>,
     context: Option<CanvasRenderingContext2d>,
     canvas: Option<HtmlCanvasElement>,
-    cache: RefCell<Vec<Vec<(f64, Option<f64>)>>>,
+    cache: RefCell<Vec<PointCache>>,
     delta: f64,
-    polar_mode: bool
+    //values of the parameters set from JS - kept across calls to reset, so that they survive the expressions being edited
+    parameters: RefCell<Vec<(String, f64)>>,
+    //settings set from JS for each input - kept across calls to reset, as for parameters
+    settings: RefCell<Vec<InputSettings>>,
+    //viewport passed to the last call to run, used to redraw when a parameter changes
+    viewport: (f64, f64, f64, f64)
 }
 
 //global app state
 thread_local! {
-    static APP_STATE : RefCell<AppState> = RefCell::new(AppState { 
+    static APP_STATE : RefCell<AppState> = const { RefCell::new(AppState { 
         calculators: RefCell::new(Vec::new()), 
+        parametric_curves: RefCell::new(Vec::new()),
         context: None, 
         canvas: None, 
         cache: RefCell::new(Vec::new()),
         delta: 0.001953125,
-        polar_mode: false
-    });
+        parameters: RefCell::new(Vec::new()),
+        settings: RefCell::new(Vec::new()),
+        viewport: (-5.0, 5.0, -5.0, 5.0)
+    }) };
 }
 
 const MAX_CACHE_SIZE : usize = 100000;
+//step size used to graph functions in polar mode, whatever the size of the viewport
+const POLAR_STEP_SIZE: f64 = 0.001953125;
 
 //main function called from JS
 #[wasm_bindgen]
 pub fn run(x_start: f64, x_end: f64, y_start: f64, y_end: f64) {
     APP_STATE.with(|state| {
         set_delta(x_start, x_end);
+        state.borrow_mut().viewport = (x_start, x_end, y_start, y_end);
         let s = state.borrow();
         let canvas = s.canvas.as_ref().unwrap();
         let context = s.context.as_ref().unwrap();
-        reset_canvas(&canvas, &context);
-        transform_canvas(&canvas, &context, x_start, x_end, y_start, y_end);
-        draw_initial_grid(&context, x_start, x_end, y_start, y_end, 1);
+        reset_canvas(canvas, context);
+        transform_canvas(canvas, context, x_start, x_end, y_start, y_end);
+        draw_initial_grid(context, x_start, x_end, y_start, y_end, 1);
         //note: draw_initial_grid should leave rendering context with the correct line width for graph_each_function - the width of the main graph axes should equal the width of the function lines 
-        graph_each_function(&context, x_start.floor(), x_end.ceil(), y_start, y_end);
+        graph_each_function(context, x_start, x_end, y_start, y_end);
     })
 }
 
@@ -59,38 +183,22 @@ pub fn reset() {
         s.canvas.take();
         s.context.take();
         s.calculators.borrow_mut().clear();
+        s.parametric_curves.borrow_mut().clear();
         s.cache.borrow_mut().clear();
     });
 }
 
-/*sets up Calculator structs for each expression passed in from JS - returns array of booleans indicating whether a Calculator was 
-successfully initialize for each struct*/
+/*sets up Calculator structs for each expression passed in from JS - returns an array with one Diagnostic (see error.rs) per expression, 
+describing how the expression was parsed, or why it couldn't be*/
 #[wasm_bindgen]
 pub fn initialize(expressions: JsValue) -> JsValue {
     APP_STATE.with(|state| {
-        let mut result = Vec::new();
         let mut s = state.borrow_mut();
         //convert array of expression strings from JS array to Rust vector
         let expressions: Vec<String> = serde_wasm_bindgen::from_value(expressions).unwrap();
-        //scan, parse, and generate calculator for each expression and add to global state
-        for expression in expressions.iter() {
-            let tokens = scan(expression);
-            if let Err(_e) = tokens { 
-                result.push(false);
-                continue;
-            }
-            let tokens = tokens.unwrap();
-            let ast = parse(&tokens);
-            if let Err(_e) = ast {
-                result.push(false);
-                continue;
-            }
-            let ast = ast.unwrap();
-            let calculator = generate_calculator(ast, s.delta * 0.5);
-            s.calculators.borrow_mut().push(calculator);
-            s.cache.borrow_mut().push(Vec::new());
-            result.push(true);
-        }
+        let (entries, result) = build_entries(&expressions, &s.parameters.borrow(), &s.settings.borrow());
+        s.cache.borrow_mut().extend(entries.iter().map(|_| Vec::new()));
+        s.calculators.borrow_mut().extend(entries);
         let (canvas, context) = initialize_canvas();
         s.canvas.replace(canvas);
         s.context.replace(context);
@@ -99,36 +207,141 @@ pub fn initialize(expressions: JsValue) -> JsValue {
     })
 }
 
+/*function to scan and parse each expression, expanding references between them, and generate a function entry for each - each entry starts
+with its parameters at the given values and with the settings given for its input. Also produces one Diagnostic (see error.rs) per 
+expression*/
+fn build_entries(expressions: &[String], parameters: &[(String, f64)], settings: &[InputSettings]) -> (Vec<Option<FunctionEntry>>, Vec<Diagnostic>) {
+    let mut entries = Vec::new();
+    let mut result = Vec::new();
+    for (i, definition) in resolve_definitions(expressions).into_iter().enumerate() {
+        //empty inputs still take up a slot, so that indices line up with the inputs on the JS side
+        let Some(definition) = definition else {
+            entries.push(None);
+            result.push(Diagnostic::success(String::new(), Vec::new()));
+            continue;
+        };
+        match definition.and_then(build_calculator) {
+            Ok((mut entry, parsed, derivatives)) => {
+                //start each parameter at the value it was last set to, and use the settings last set for this input
+                for (name, value) in parameters {
+                    for calculator in entry.calculators() {
+                        calculator.set_parameter(name, *value);
+                    }
+                }
+                entry.settings = settings.get(i).copied().unwrap_or_default();
+                result.push(Diagnostic { derivatives, ..Diagnostic::success(parsed, entry.parameters()) });
+                entries.push(Some(entry));
+            }
+            Err(e) => {
+                entries.push(None);
+                result.push(Diagnostic::failure(e));
+            }
+        }
+    }
+    (entries, result)
+}
+
+/*sets up Calculator structs for each parametric curve passed in from JS, replacing any previous curves - expects initialize to have been 
+called first. Returns an array with one ParametricDiagnostic (see error.rs) per curve, describing how each of its coordinates was parsed,
+or why it couldn't be*/
+#[wasm_bindgen]
+pub fn initialize_parametric(curves: JsValue) -> JsValue {
+    APP_STATE.with(|state| {
+        let s = state.borrow();
+        let mut result = Vec::new();
+        let curves: Vec<ParametricInput> = serde_wasm_bindgen::from_value(curves).unwrap();
+        let mut parametric_curves = s.parametric_curves.borrow_mut();
+        parametric_curves.clear();
+        for curve in curves {
+            //curves with both coordinates empty still take up a slot, as for initialize
+            if curve.x.trim().is_empty() && curve.y.trim().is_empty() {
+                let empty = Diagnostic::success(String::new(), Vec::new());
+                result.push(ParametricDiagnostic { x: empty.clone(), y: empty });
+                parametric_curves.push(None);
+                continue;
+            }
+            let x = build_coordinate(&curve.x, &s.parameters.borrow());
+            let y = build_coordinate(&curve.y, &s.parameters.borrow());
+            let diagnostic = |coordinate: &Result<(Calculator, String), ExpressionError>| match coordinate {
+                Ok((calculator, parsed)) => Diagnostic::success(parsed.clone(), calculator.parameters().to_vec()),
+                Err(e) => Diagnostic::failure(e.clone())
+            };
+            result.push(ParametricDiagnostic { x: diagnostic(&x), y: diagnostic(&y) });
+            match (x, y) {
+                (Ok((x, _)), Ok((y, _))) => parametric_curves.push(Some(ParametricCurve { x, y, t_start: curve.t_start, t_end: curve.t_end })),
+                _ => parametric_curves.push(None)
+            }
+        }
+        serde_wasm_bindgen::to_value(&result).unwrap()
+    })
+}
+
+//function to generate a Calculator for one coordinate of a parametric curve, starting at the given parameter values - also produces the coordinate as it was parsed
+fn build_coordinate(expression: &str, parameters: &[(String, f64)]) -> Result<(Calculator, String), ExpressionError> {
+    let expression = resolve_parametric(expression)?;
+    let parsed = expression.to_string();
+    let mut calculator = generate_calculator(expression)?;
+    for (name, value) in parameters {
+        calculator.set_parameter(name, *value);
+    }
+    Ok((calculator, parsed))
+}
+
+/*function to generate a function entry for an expanded input, graphed over its default range of θ - also passes through the parsed input in
+normalized form, and the derivatives of a function as they were simplified*/
+fn build_calculator(resolved: ResolvedInput) -> Result<(FunctionEntry, String, Vec<String>), ExpressionError> {
+    let default_theta_range = default_theta_range(&resolved.expression);
+    //only functions are differentiated - regions and implicit curves aren't functions of one variable
+    let derived = match resolved.kind {
+        PlotKind::Function(_) => derivatives(&resolved.expression, 2),
+        _ => Vec::new()
+    };
+    let parsed_derivatives = derived.iter().map(|derivative| derivative.to_string()).collect();
+    let derivatives = derived.into_iter().map(generate_calculator).collect::<Result<_, _>>()?;
+    let graph = match resolved.kind {
+        PlotKind::Points => Graph::Points(generate_point_calculators(resolved.expression)?),
+        _ => Graph::Function(generate_calculator(resolved.expression)?)
+    };
+    let entry = FunctionEntry { graph, kind: resolved.kind, settings: InputSettings::default(), derivatives, default_theta_range };
+    Ok((entry, resolved.parsed, parsed_derivatives))
+}
+
 //function to precalculate points for the current graphed functions outside of the current visible graph viewport - returns true if all caches full, false otherwise
 #[wasm_bindgen]
 pub fn expand_cache() -> bool {
     APP_STATE.with(|state| {
         let s = state.borrow();
-        //if we're in polar mode, don't expand cache
-        if s.polar_mode { return true; }
         let mut cache = s.cache.borrow_mut();
         let mut calculators = s.calculators.borrow_mut();
         let mut caches_full = true;
-        if cache.len() > 0 {
-            for (i, calculator) in calculators.iter_mut().enumerate() {
-                //expect each function's cache to already contiain the points in the current graph viewport
-                if cache[i].len() == 0 || cache[i].len() >= MAX_CACHE_SIZE { continue; }
+        if !cache.is_empty() {
+            for (i, entry) in calculators.iter_mut().enumerate() {
+                //lists of points aren't cached
+                let Some(FunctionEntry { graph: Graph::Function(calculator), .. }) = entry else { continue; };
+                //expect each function's cache to already contiain the points in the current graph viewport - functions graphed in polar mode aren't cached
+                if cache[i].is_empty() || cache[i].len() >= MAX_CACHE_SIZE { continue; }
                 caches_full = false;
                 let cache_start = cache[i][0].0;
                 let cache_end = cache[i][cache[i].len() - 1].0;
                 let mut prepend = Vec::new();
                 let mut append = Vec::new();
                 //expand cache to the left of current viewport
+                let mut last_y = calculator.calculate(cache_start - s.delta * 51.0);
                 for j in (1..=50).rev() {
                     let x = cache_start - s.delta * j as f64;
                     let y = calculator.calculate(x);
-                    prepend.push((x,y));
+                    let joined = is_joined(calculator, (x - s.delta, last_y), (x, y));
+                    last_y = y;
+                    prepend.push((x, y, joined));
                 }
                 //expand cache to the right of current viewport
+                let mut last_y = cache[i][cache[i].len() - 1].1;
                 for j in 1..=50 {
                     let x = cache_end + s.delta * j as f64;
                     let y = calculator.calculate(x);
-                    append.push((x,y));
+                    let joined = is_joined(calculator, (x - s.delta, last_y), (x, y));
+                    last_y = y;
+                    append.push((x, y, joined));
                 }
                 prepend.append(&mut cache[i]);
                 prepend.append(&mut append);
@@ -139,42 +352,215 @@ pub fn expand_cache() -> bool {
     })
 }
 
-//function to be called from JS to toggle between cartesian and polar modes
+/*function to be called from JS to set the value of a parameter and redraw - only the cached points of functions that use the parameter are
+invalidated, and no expressions are rescanned or reparsed*/
 #[wasm_bindgen]
-pub fn toggle_mode() {
+pub fn set_parameter(name: String, value: f64) {
     APP_STATE.with(|state| {
-        let mut s = state.borrow_mut();
-        if s.polar_mode {
-            s.polar_mode = false;
+        let s = state.borrow();
+        let mut parameters = s.parameters.borrow_mut();
+        match parameters.iter_mut().find(|(parameter, _)| *parameter == name) {
+            Some(parameter) => parameter.1 = value,
+            None => parameters.push((name.clone(), value))
         }
-        else {
-            s.polar_mode = true;
+        apply_parameter(&mut s.calculators.borrow_mut(), &mut s.cache.borrow_mut(), &name, value);
+        //parametric curves aren't cached, so only need their values updated
+        for curve in s.parametric_curves.borrow_mut().iter_mut().flatten() {
+            curve.x.set_parameter(&name, value);
+            curve.y.set_parameter(&name, value);
         }
-        //clear cached points on mode switch
-        for c in s.cache.borrow_mut().iter_mut() {
-            c.clear();
+    });
+    redraw();
+}
+
+/*function to set the value of a parameter in every Calculator of each entry, including its derivatives - only the cached points of entries
+that use the parameter are cleared*/
+fn apply_parameter(entries: &mut [Option<FunctionEntry>], cache: &mut [PointCache], name: &str, value: f64) {
+    for (i, entry) in entries.iter_mut().enumerate() {
+        let Some(entry) = entry else { continue; };
+        for calculator in entry.calculators() {
+            if calculator.set_parameter(name, value) { cache[i].clear(); }
+        }
+    }
+}
+
+//function to be called from JS to get the current value of a parameter
+#[wasm_bindgen]
+pub fn get_parameter(name: String) -> f64 {
+    APP_STATE.with(|state| {
+        let s = state.borrow();
+        let parameters = s.parameters.borrow();
+        parameters.iter().find(|(parameter, _)| *parameter == name).map_or(DEFAULT_PARAMETER_VALUE, |parameter| parameter.1)
+    })
+}
+
+/*function to be called from JS to set the coordinates the function at index is graphed in, and redraw - only that function's cached points 
+are cleared, and the mode is kept when the function is edited*/
+#[wasm_bindgen]
+pub fn set_mode(index: usize, mode: CoordinateMode) {
+    update_settings(index, |settings| settings.mode = mode);
+}
+
+/*function to be called from JS to set the range of θ the function at index is graphed over in polar mode, replacing its default range, and
+redraw - the range is kept when the function is edited*/
+#[wasm_bindgen]
+pub fn set_theta_range(index: usize, theta_start: f64, theta_end: f64) {
+    update_settings(index, |settings| settings.theta_range = Some((theta_start, theta_end)));
+}
+
+//function to be called from JS to set the shape of the markers drawn for the list of points at index, and redraw
+#[wasm_bindgen]
+pub fn set_marker(index: usize, shape: MarkerShape) {
+    update_settings(index, |settings| settings.marker = shape);
+}
+
+//function to be called from JS to set whether the first and second derivatives of the function at index are drawn under it, and redraw
+#[wasm_bindgen]
+pub fn set_derivatives(index: usize, show: bool) {
+    update_settings(index, |settings| settings.show_derivatives = show);
+}
+
+//function to be called from JS to graph the function at index over its default range of θ again, and redraw
+#[wasm_bindgen]
+pub fn clear_theta_range(index: usize) {
+    update_settings(index, |settings| settings.theta_range = None);
+}
+
+//function to change the settings of the input at index, both those kept for it and those of its function if it has one, and redraw
+fn update_settings(index: usize, update: impl Fn(&mut InputSettings)) {
+    APP_STATE.with(|state| {
+        let s = state.borrow();
+        apply_settings(&mut s.settings.borrow_mut(), &mut s.calculators.borrow_mut(), &mut s.cache.borrow_mut(), index, update);
+    });
+    redraw();
+}
+
+/*function to change the settings kept for the input at index, and those of its entry if it has one - only that entry's cached points are 
+cleared, and only if its mode changes*/
+fn apply_settings(settings: &mut Vec<InputSettings>, entries: &mut [Option<FunctionEntry>], cache: &mut [PointCache], index: usize, update: impl Fn(&mut InputSettings)) {
+    if settings.len() <= index { settings.resize(index + 1, InputSettings::default()); }
+    update(&mut settings[index]);
+    if let Some(Some(entry)) = entries.get_mut(index) {
+        let mode = entry.settings.mode;
+        update(&mut entry.settings);
+        //points cached in one mode can't be drawn in another
+        if entry.settings.mode != mode { cache[index].clear(); }
+    }
+}
+
+//function to be called from JS to get the range of θ the function at index is graphed over, as [start, end] - empty if there is no function at index
+#[wasm_bindgen]
+pub fn get_theta_range(index: usize) -> Vec<f64> {
+    APP_STATE.with(|state| {
+        let s = state.borrow();
+        let calculators = s.calculators.borrow();
+        match calculators.get(index) {
+            Some(Some(entry)) => {
+                let (theta_start, theta_end) = entry.theta_range();
+                vec![theta_start, theta_end]
+            }
+            _ => Vec::new()
         }
     })
 }
 
-//function to graph each function in global state - if that function's cache contains all needed points, use the cache, otherwise, calculate points as we go
+/*function to be called from JS to calculate the function at index and its derivative at x, as [f(x), f′(x)] - empty if there is no function
+at index, or if it or its derivative is undefined at x. A function of y is differentiated with respect to y*/
+#[wasm_bindgen]
+pub fn get_derivative(index: usize, x: f64) -> Vec<f64> {
+    APP_STATE.with(|state| {
+        let s = state.borrow();
+        let calculators = s.calculators.borrow();
+        match calculators.get(index) {
+            Some(Some(FunctionEntry { graph: Graph::Function(calculator), .. })) => {
+                calculator.calculate_derivative(x).map_or(Vec::new(), |(value, derivative)| vec![value, derivative])
+            }
+            _ => Vec::new()
+        }
+    })
+}
+
+//function to redraw with the viewport passed to the last call to run - nothing has been drawn yet if there is no canvas
+fn redraw() {
+    let (x_start, x_end, y_start, y_end) = APP_STATE.with(|state| state.borrow().viewport);
+    if APP_STATE.with(|state| state.borrow().canvas.is_some()) {
+        run(x_start, x_end, y_start, y_end);
+    }
+}
+
+/*function to graph each function in global state - if that function's cache contains all needed points, use the cache, otherwise, calculate points as we go.
+Each function's cache holds points along the axis it is sampled along*/
 fn graph_each_function(context: &CanvasRenderingContext2d, x_start: f64, x_end: f64, y_start: f64, y_end: f64)  {
     APP_STATE.with(|state| {
         let s = state.borrow();
         let mut cache = s.cache.borrow_mut();
-        for (i, calculator) in s.calculators.borrow_mut().iter_mut().enumerate() {
-            if cache.len() > i && cache[i].len() > 0 && cache[i][0].0 <= x_start && cache[i][cache[i].len() - 1].0 >= x_end {
-                draw_function_graph_from_cache(context, &cache[i], x_start, x_end, y_start, y_end, s.delta, i)
+        for (i, entry) in s.calculators.borrow_mut().iter_mut().enumerate() {
+            let Some(entry) = entry else { continue; };
+            let (theta_start, theta_end) = entry.theta_range();
+            //functions of y, regions and implicit curves are always graphed in cartesian coordinates
+            let polar = entry.is_polar();
+            let calculator = match &mut entry.graph {
+                Graph::Function(calculator) => calculator,
+                Graph::Points(points) => {
+                    draw_points(context, points, x_start, x_end, y_start, y_end, i, entry.settings.marker);
+                    continue;
+                }
+            };
+            //derivatives are drawn under the function, in lighter shades of its color, and aren't cached
+            if let (true, PlotKind::Function(axis)) = (entry.settings.show_derivatives, entry.kind) {
+                for (order, derivative) in entry.derivatives.iter_mut().enumerate() {
+                    set_derivative_style(context, order + 1);
+                    if polar { draw_function_graph_polar(context, derivative, x_start, x_end, y_start, y_end, POLAR_STEP_SIZE, i, theta_start, theta_end); }
+                    else { draw_function_graph(context, derivative, &mut Vec::new(), x_start, x_end, y_start, y_end, s.delta, i, axis); }
+                }
+                set_derivative_style(context, 0);
+            }
+            if polar {
+                draw_function_graph_polar(context, calculator, x_start, x_end, y_start, y_end, POLAR_STEP_SIZE, i, theta_start, theta_end);
+                continue;
+            }
+            let kind = &entry.kind;
+            let (axis, region) = match *kind {
+                PlotKind::Function(axis) => (axis, None),
+                PlotKind::FunctionRegion(axis, inequality) => (axis, Some(inequality)),
+                //implicit curves and regions are drawn across the whole viewport, and aren't cached
+                PlotKind::Implicit | PlotKind::ImplicitRegion(_) => {
+                    if let PlotKind::ImplicitRegion(inequality) = kind {
+                        draw_implicit_region(context, calculator, x_start, x_end, y_start, y_end, i, *inequality);
+                        set_boundary_style(context, Some(*inequality), x_start, x_end);
+                    }
+                    draw_implicit_curve(context, calculator, x_start, x_end, y_start, y_end, i);
+                    set_boundary_style(context, None, x_start, x_end);
+                    continue;
+                }
+                //lists of points are drawn above
+                PlotKind::Points => continue
+            };
+            //round the sampled range out to whole numbers, so that sampled points line up with cached ones
+            let (x_start, x_end, y_start, y_end) = match axis {
+                Axis::X => (x_start.floor(), x_end.ceil(), y_start, y_end),
+                Axis::Y => (x_start, x_end, y_start.floor(), y_end.ceil())
+            };
+            let (start, end, _, _) = axis.bounds(x_start, x_end, y_start, y_end);
+            //regions are filled first, then their boundary is drawn like any other function
+            if let Some(inequality) = region {
+                draw_function_region(context, calculator, x_start, x_end, y_start, y_end, s.delta, i, axis, inequality);
+                set_boundary_style(context, region, x_start, x_end);
+            }
+            if cache.len() > i && !cache[i].is_empty() && cache[i][0].0 <= start && cache[i][cache[i].len() - 1].0 >= end {
+                draw_function_graph_from_cache(context, &cache[i], x_start, x_end, y_start, y_end, s.delta, i, axis)
             }
             else {
                 if cache.len() > i { cache[i].clear(); }
-                if s.polar_mode {
-                    draw_function_graph_polar(context, calculator, x_start, x_end, y_start, y_end, s.delta, i);
-                }
-                else {
-                    draw_function_graph(context, calculator, &mut cache[i], x_start, x_end, y_start, y_end, s.delta, i);
-                }
+                draw_function_graph(context, calculator, &mut cache[i], x_start, x_end, y_start, y_end, s.delta, i, axis);
             }
+            if region.is_some() { set_boundary_style(context, None, x_start, x_end); }
+        }
+        //parametric curves aren't cached
+        let functions = s.calculators.borrow().len();
+        for (i, curve) in s.parametric_curves.borrow_mut().iter_mut().enumerate() {
+            let Some(curve) = curve else { continue; };
+            draw_parametric_curve(context, &mut curve.x, &mut curve.y, curve.t_start, curve.t_end, x_start, x_end, y_start, y_end, functions + i);
         }
     })   
 }
@@ -184,11 +570,7 @@ fn set_delta(x_start: f64, x_end: f64) {
     APP_STATE.with(|state| {
         let mut s = state.borrow_mut();
         let mut clear_cache = false;
-        //if we're in polar mode, always use smaller delta
-        if s.polar_mode {
-            s.delta = 0.001953125;
-        }
-        else if x_end - x_start > 20.0 {
+        if x_end - x_start > 20.0 {
             if s.delta == 0.001953125 {
                 clear_cache = true;
             }
@@ -207,4 +589,66 @@ fn set_delta(x_start: f64, x_end: f64) {
             }
         }
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn entries(inputs: &[&str], settings: &[InputSettings]) -> Vec<Option<FunctionEntry>> {
+        let expressions: Vec<String> = inputs.iter().map(|input| input.to_string()).collect();
+        build_entries(&expressions, &[], settings).0
+    }
+
+    fn modes(entries: &[Option<FunctionEntry>]) -> Vec<bool> {
+        entries.iter().map(|entry| entry.as_ref().is_some_and(FunctionEntry::is_polar)).collect()
+    }
+
+    #[test]
+    fn test_set_parameter_clears_caches() {
+        let inputs = ["g(x) = a x^2", "g(x) + 1", "b x", "", "(a, 1)"];
+        let mut functions = entries(&inputs, &[]);
+        let mut cache: Vec<PointCache> = vec![vec![(0.0, Some(0.0), true)]; inputs.len()];
+        apply_parameter(&mut functions, &mut cache, "a", 3.0);
+        //g(x) + 1 uses a through the expanded definition of g
+        assert_eq!(cache.iter().map(Vec::is_empty).collect::<Vec<_>>(), vec![true, true, false, false, true]);
+        //and its derivatives are updated along with it
+        let entry = functions[1].as_mut().unwrap();
+        assert_eq!(entry.derivatives.iter_mut().map(|derivative| derivative.calculate(1.0).unwrap()).collect::<Vec<_>>(), vec![6.0, 6.0]);
+        let Graph::Function(calculator) = &mut entry.graph else { panic!("expected a function"); };
+        assert_eq!(calculator.calculate(1.0), Some(4.0));
+        //setting a parameter no function uses leaves every cache alone
+        let mut cache: Vec<PointCache> = vec![vec![(0.0, Some(0.0), true)]; inputs.len()];
+        apply_parameter(&mut functions, &mut cache, "c", 2.0);
+        assert!(cache.iter().all(|points| !points.is_empty()));
+    }
+
+    #[test]
+    fn test_set_mode_per_function() {
+        let inputs = ["sin(x)", "x^2", "", "2x"];
+        let mut settings = Vec::new();
+        let mut functions = entries(&inputs, &settings);
+        let mut cache: Vec<PointCache> = vec![vec![(0.0, Some(0.0), true)]; inputs.len()];
+        apply_settings(&mut settings, &mut functions, &mut cache, 1, |settings| settings.mode = CoordinateMode::Polar);
+        assert_eq!(modes(&functions), vec![false, true, false, false]);
+        assert_eq!(cache.iter().map(Vec::is_empty).collect::<Vec<_>>(), vec![false, true, false, false]);
+        //a setting that doesn't change the mode leaves the cache alone
+        apply_settings(&mut settings, &mut functions, &mut cache, 3, |settings| settings.theta_range = Some((0.0, PI)));
+        apply_settings(&mut settings, &mut functions, &mut cache, 3, |settings| settings.mode = CoordinateMode::Cartesian);
+        assert!(!cache[3].is_empty());
+        //settings are kept for each input when the inputs are initialized again, including for inputs past the end
+        apply_settings(&mut settings, &mut functions, &mut cache, 5, |settings| settings.mode = CoordinateMode::Polar);
+        let functions = entries(&["sin(x)", "x^2", "", "2x", "x", "x"], &settings);
+        assert_eq!(modes(&functions), vec![false, true, false, false, false, true]);
+        assert_eq!(functions[3].as_ref().unwrap().theta_range(), (0.0, PI));
+    }
+
+    #[test]
+    fn test_polar_only_for_functions_of_x() {
+        let polar = InputSettings { mode: CoordinateMode::Polar, ..InputSettings::default() };
+        let functions = entries(&["x^2", "x = y^2", "y < x", "x^2 + y^2 = 1"], &[polar; 4]);
+        assert!(functions.iter().all(Option::is_some));
+        assert_eq!(modes(&functions), vec![true, false, false, false]);
+    }
+}
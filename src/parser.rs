@@ -1,31 +1,214 @@
 /*
 GRAMMAR RECOGNIZED BY PARSER:
+<Statement> ::= name ( variable ) = <Expr> | <Expr> = <Expr> | <Comparison> | <Points> | <Expr>
+
+<Points> ::= <Points> , <Point> | <Point>
+
+<Point> ::= ( <Expr> , <Expr> )
+
+<Comparison> ::= <Expr> < <Expr> | <Expr> <= <Expr> | <Expr> > <Expr> | <Expr> >= <Expr>
+
 <Expr> ::= <Expr> + <Term> | <Expr> - <Term> | <Term>
 
-<Term> ::= <Term> * <Fact> | <Term> / <Fact> | <Fact>
+<Term> ::= <Term> * <Implicit> | <Term> / <Implicit> | <Term> mod <Implicit> | <Implicit>
+
+<Implicit> ::= <Implicit> <Pow> | <Unary>
 
 <Unary> ::= - <Unary> | <Pow>
 
-<Pow> ::= <Func> ^ <Pow> | <Func>
+<Pow> ::= <Postfix> ^ <Exponent> | <Postfix>
+
+<Exponent> ::= - <Exponent> | <Pow>
+
+<Postfix> ::= <Postfix> ! | <Func>
+
+<Func> ::= func_name ( <Args> ) | user_func_name ( <Expr> ) | if ( <Condition> , <Expr> ) | if ( <Condition> , <Expr> , <Expr> ) 
+           | √ <Pow> | <Atom>
+
+<Args> ::= <Args> , <Expr> | <Expr>
+
+<Atom> ::= variable | parameter | constant | num | ( <Expr> ) | <Point> | "|" <Expr> "|" | { <Branches> }
+
+<Branches> ::= <Branch> , <Branches> | <Branch> | <Expr>
+
+<Branch> ::= <Condition> : <Expr> | <Condition>
+
+<Condition> ::= <Condition> or <Conjunction> | <Conjunction>
 
-<Func> ::= func_name ( <Expr> ) | <Atom>
+<Conjunction> ::= <Conjunction> and <Chain> | <Chain>
 
-<Atom> ::= variable | constant | num | ( <Expr> )
+<Chain> ::= <Chain> < <Expr> | <Chain> <= <Expr> | <Chain> > <Expr> | <Chain> >= <Expr> | <Comparison>
+
+IMPLICIT MULTIPLICATION:
+Two operands written next to each other (2x, 3sin(x), (x+1)(x-1), 2pi) are multiplied. Implicit multiplication binds more
+tightly than explicit * and /, but less tightly than unary minus and ^, so:
+    2x^2    is  2 * (x^2)
+    1/2x    is  1 / (2 * x)
+    2x/3    is  (2 * x) / 3
+    -2x     is  (-2) * x
+    2^3x    is  (2^3) * x
+FACTORIAL AND MODULO:
+Postfix ! binds more tightly than ^ and unary minus, so -x! is -(x!) and 2^x! is 2^(x!). mod (or %) has the same precedence as * and /,
+and is the modulo operator wherever it follows an operand (see classify_mod in scanner.rs), so x mod 3 and x % 3 are the same, while
+mod(x, 3) is still a function call.
+
+ABSOLUTE VALUE:
+|x| produces the same AST node as abs(x). The scanner decides whether each | opens or closes an absolute value (see classify_pipe in 
+scanner.rs), so |x||y| is abs(x) * abs(y), and ||x|-1| is abs(abs(x) - 1).
+
+The number of arguments passed to each function is checked against the arity of that function (see function_arity in scanner.rs).
+
+Only a token that can start an operand (number, constant, variable, function name, √, left parenthesis or left brace) triggers implicit
+multiplication, so x -1 is still a subtraction.
+
+NAMED FUNCTIONS:
+An input of the form g(x) = x^2 + 1 defines a function that other inputs can call as g( ), and every input can be called by its
+position as f1( ) to f5( ) (see definition.rs). A parameter is only scanned as a call to a user defined function if some input defines 
+a function with its name, so a(x + 1) is still a * (x + 1).
+
+EQUATIONS:
+An input containing = is an equation. Equations of the form y = f(x) are graphed like f(x), equations of the form x = g(y),
+including vertical lines such as x = 3, are graphed as functions of y, and any other equation is graphed as an implicit curve (see 
+definition.rs).
+
+INEQUALITIES:
+An input that compares two expressions with <, <=, > or >= (or ≤ and ≥) is an inequality, and is graphed as the region where it holds
+(see definition.rs).
+
+PIECEWISE EXPRESSIONS:
+{x < 0: -x, x >= 0: sqrt(x)} takes the value of the first branch whose condition holds, or of the last branch if it has no condition, 
+and is undefined where no branch applies. A branch with no value is 1 where its condition holds, so x^2 {x < 0} (implicitly multiplied)
+is x^2 restricted to x < 0, and if(x > 1, 1/x, x) is {x > 1: 1/x, x}. and (∧) binds more tightly than or (∨), and a chain such as
+0 < x < 1 holds where each comparison in it holds.
+
+POINTS:
+An input that is a comma separated list of points, such as (1, 2.1), (2, 3.9), is graphed as a marker at each point. Coordinates can use
+parameters, but points can't be used in any other expression (see generate_instructions in calculator.rs).
+
+UNICODE INPUT:
+The scanner turns unicode math symbols into the same tokens as their ascii forms (π is pi, θ is theta, × and · are *, ÷ is /, − is -,
+x² is x^2), so they produce the same AST. √ is a prefix square root that applies to the power expression after it, so √x^2 is sqrt(x^2)
+and √2x is sqrt(2) * x, and it produces the same AST node as sqrt( ).
 */
 
-use crate::scanner::TokenType;
+use std::fmt;
+
+use crate::{error::ParseError, scanner::{function_arity, Span, Token, TokenType}};
+
+//node of the AST, along with the span of input characters it was parsed from
+#[derive(Debug, PartialEq, Clone)]
+pub struct ASTNode {
+    pub node_type: ASTNodeType,
+    pub span: Span
+}
+
+impl ASTNode {
+    pub fn new(node_type: ASTNodeType, span: Span) -> ASTNode {
+        ASTNode { node_type, span }
+    }
+
+    //function to get the child nodes of this node, in order
+    pub fn children(&self) -> Vec<&ASTNode> {
+        match &self.node_type {
+            ASTNodeType::BinaryExpression(_, left, right) | ASTNodeType::InequalityExpression(_, left, right) 
+            | ASTNodeType::LogicalExpression(_, left, right) | ASTNodeType::PointExpression(left, right) => vec![left, right],
+            ASTNodeType::UnaryExpression(_, operand) => vec![operand],
+            ASTNodeType::FunctionExpression(_, arguments) | ASTNodeType::ListExpression(arguments) => arguments.iter().collect(),
+            ASTNodeType::AtomicExpression(_) => Vec::new(),
+            ASTNodeType::PiecewiseExpression(branches, default) => {
+                branches.iter().flat_map(|(condition, value)| [condition, value]).chain(default.as_deref()).collect()
+            }
+        }
+    }
+
+    //function to produce a node type like this node's, with each child replaced by the result of f on it - stops at the first error
+    pub fn try_map_children<E>(&self, mut f: impl FnMut(&ASTNode) -> Result<ASTNode, E>) -> Result<ASTNodeType, E> {
+        Ok(match &self.node_type {
+            ASTNodeType::BinaryExpression(operator, left, right) => ASTNodeType::BinaryExpression(operator.clone(), Box::new(f(left)?), Box::new(f(right)?)),
+            ASTNodeType::InequalityExpression(operator, left, right) => {
+                ASTNodeType::InequalityExpression(operator.clone(), Box::new(f(left)?), Box::new(f(right)?))
+            }
+            ASTNodeType::LogicalExpression(operator, left, right) => ASTNodeType::LogicalExpression(operator.clone(), Box::new(f(left)?), Box::new(f(right)?)),
+            ASTNodeType::UnaryExpression(operator, operand) => ASTNodeType::UnaryExpression(operator.clone(), Box::new(f(operand)?)),
+            ASTNodeType::FunctionExpression(name, arguments) => {
+                ASTNodeType::FunctionExpression(name.clone(), arguments.iter().map(&mut f).collect::<Result<_, _>>()?)
+            }
+            ASTNodeType::AtomicExpression(token) => ASTNodeType::AtomicExpression(token.clone()),
+            ASTNodeType::PointExpression(x, y) => ASTNodeType::PointExpression(Box::new(f(x)?), Box::new(f(y)?)),
+            ASTNodeType::ListExpression(points) => ASTNodeType::ListExpression(points.iter().map(&mut f).collect::<Result<_, _>>()?),
+            ASTNodeType::PiecewiseExpression(branches, default) => {
+                let branches = branches.iter().map(|(condition, value)| Ok((f(condition)?, f(value)?))).collect::<Result<_, _>>()?;
+                let default = match default {
+                    Some(default) => Some(Box::new(f(default)?)),
+                    None => None
+                };
+                ASTNodeType::PiecewiseExpression(branches, default)
+            }
+        })
+    }
+
+    //like try_map_children, for an f that can't fail
+    pub fn map_children(&self, mut f: impl FnMut(&ASTNode) -> ASTNode) -> ASTNodeType {
+        match self.try_map_children(|child| Ok::<ASTNode, std::convert::Infallible>(f(child))) {
+            Ok(node_type) => node_type,
+            Err(never) => match never {}
+        }
+    }
+}
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum ASTNodeType {
-    BinaryExpression(TokenType, Box<ASTNodeType>, Box<ASTNodeType>),
-    UnaryExpression(TokenType, Box<ASTNodeType>),
-    FunctionExpression(TokenType, Box<ASTNodeType>),
-    AtomicExpression(TokenType)
+    BinaryExpression(TokenType, Box<ASTNode>, Box<ASTNode>),
+    UnaryExpression(TokenType, Box<ASTNode>),
+    FunctionExpression(TokenType, Vec<ASTNode>),
+    AtomicExpression(TokenType),
+    InequalityExpression(TokenType, Box<ASTNode>, Box<ASTNode>),
+    LogicalExpression(TokenType, Box<ASTNode>, Box<ASTNode>),
+    //branches of (condition, value) in order, and the value used when no condition holds, if any
+    PiecewiseExpression(Vec<(ASTNode, ASTNode)>, Option<Box<ASTNode>>),
+    PointExpression(Box<ASTNode>, Box<ASTNode>),
+    //a list of points, each a PointExpression
+    ListExpression(Vec<ASTNode>)
+}
+
+impl fmt::Display for ASTNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.node_type)
+    }
+}
+
+//prints the expression fully parenthesized, so that the way it was parsed is unambiguous
+impl fmt::Display for ASTNodeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ASTNodeType::BinaryExpression(operator, left, right) => write!(f, "({} {} {})", left, operator, right),
+            ASTNodeType::UnaryExpression(TokenType::Factorial, expression) => write!(f, "({}!)", expression),
+            ASTNodeType::UnaryExpression(operator, expression) => write!(f, "({}{})", operator, expression),
+            ASTNodeType::FunctionExpression(name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+                write!(f, "{}({})", name, arguments.join(", "))
+            },
+            ASTNodeType::AtomicExpression(token) => write!(f, "{}", token),
+            ASTNodeType::InequalityExpression(operator, left, right) => write!(f, "{} {} {}", left, operator, right),
+            ASTNodeType::LogicalExpression(operator, left, right) => write!(f, "({} {} {})", left, operator, right),
+            ASTNodeType::PiecewiseExpression(branches, default) => {
+                let mut branches: Vec<String> = branches.iter().map(|(condition, value)| format!("{}: {}", condition, value)).collect();
+                if let Some(default) = default { branches.push(default.to_string()); }
+                write!(f, "{{{}}}", branches.join(", "))
+            }
+            ASTNodeType::PointExpression(x, y) => write!(f, "({}, {})", x, y),
+            ASTNodeType::ListExpression(points) => {
+                let points: Vec<String> = points.iter().map(|point| point.to_string()).collect();
+                write!(f, "{}", points.join(", "))
+            }
+        }
+    }
 }
 
 macro_rules! expect_more_tokens {
     ($tokens:ident,$start:expr) => {
-        if $start >= $tokens.len() { return Err(String::from("Error: unexpected end of input while parsing expression")); }
+        if $start >= $tokens.len() { return Err(ParseError::UnexpectedEndOfInput { span: end_of_input($tokens) }); }
     }
 }
 
@@ -39,20 +222,132 @@ macro_rules! return_if_error_or_unwrap {
 macro_rules! expect_token {
     ($tokens:ident, $idx:expr, $token_type:expr) => {
         expect_more_tokens!($tokens, $idx);
-        if $tokens[$idx] != $token_type { return Err(format!("Error: expected {}, found {}", $token_type, $tokens[$idx])); }
+        if $tokens[$idx].token_type != $token_type { 
+            return Err(ParseError::UnexpectedToken { expected: $token_type.to_string(), found: $tokens[$idx].token_type.to_string(), span: $tokens[$idx].span }); 
+        }
+    }
+}
+
+//like expect_token for a closing parenthesis or pipe, but reports running out of input as the given error about the matching opening token
+macro_rules! expect_closing {
+    ($tokens:ident, $idx:expr, $token_type:expr, $error:expr) => {
+        if $idx >= $tokens.len() { return Err($error); }
+        expect_token!($tokens, $idx, $token_type);
+    }
+}
+
+//function to get the empty span just past the last token, used to report errors at the end of input
+fn end_of_input(tokens: &[Token]) -> Span {
+    let end = tokens.last().map_or(0, |token| token.span.end);
+    Span::new(end, end)
+}
+
+/*function to parse tokens as a single expression, with no definition or equation, as for the coordinates of a parametric curve - inputs
+themselves are parsed by parse_statement*/
+pub fn parse(tokens: &[Token]) -> Result<ASTNode, ParseError> {
+    parse_from(tokens, 0)
+}
+
+/*a whole input - a plain expression, a named function definition such as g(x) = x^2 + 1, or an equation such as y = 2x + 1 - a definition
+is stored as (name, span of the name, parameter, expression)*/
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    Expression(ASTNode),
+    Definition(String, Span, String, ASTNode),
+    Equation(ASTNode, ASTNode)
+}
+
+//prints the statement with each expression fully parenthesized, as for ASTNode
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Statement::Expression(expression) => write!(f, "{}", expression),
+            Statement::Definition(name, _, parameter, expression) => write!(f, "{}({}) = {}", name, parameter, expression),
+            Statement::Equation(left, right) => write!(f, "{} = {}", left, right)
+        }
+    }
+}
+
+/*function to parse a whole input, which may define a named function or be an equation - a definition's name is produced along with its
+span and the name of its parameter*/
+pub fn parse_statement(tokens: &[Token]) -> Result<Statement, ParseError> {
+    if let Some(name) = definition_name(tokens) {
+        let parameter = tokens[2].token_type.to_string();
+        return parse_from(tokens, DEFINITION_HEAD_LENGTH).map(|expression| Statement::Definition(name, tokens[0].span, parameter, expression));
+    }
+    let left = parse_expression(tokens, 0);
+    return_if_error_or_unwrap!(left, stop);
+    if stop < tokens.len() && tokens[stop].token_type == TokenType::Equals {
+        return parse_from(tokens, stop + 1).map(|right| Statement::Equation(left, right));
+    }
+    if let ASTNodeType::PointExpression(_, _) = left.node_type {
+        return parse_points(tokens, left, stop).map(Statement::Expression);
     }
+    if stop < tokens.len() && is_comparison(&tokens[stop].token_type) {
+        let operator = tokens[stop].token_type.clone();
+        return parse_from(tokens, stop + 1).map(|right| {
+            let span = left.span.to(right.span);
+            Statement::Expression(ASTNode::new(ASTNodeType::InequalityExpression(operator, Box::new(left), Box::new(right)), span))
+        });
+    }
+    expect_end_of_input(tokens, stop)?;
+    Ok(Statement::Expression(left))
+}
+
+//function to parse the rest of a list of points, given its first point and the index of the token after it, and produce a list AST node
+fn parse_points(tokens: &[Token], first: ASTNode, start: usize) -> Result<ASTNode, ParseError> {
+    let mut points = vec![first];
+    let mut next = start;
+    while next < tokens.len() && tokens[next].token_type == TokenType::Comma {
+        let point = parse_expression(tokens, next + 1);
+        return_if_error_or_unwrap!(point, after);
+        if !matches!(point.node_type, ASTNodeType::PointExpression(_, _)) { return Err(ParseError::ExpectedPoint { span: point.span }); }
+        points.push(point);
+        next = after;
+    }
+    expect_end_of_input(tokens, next)?;
+    let span = points[0].span.to(points[points.len() - 1].span);
+    Ok(ASTNode::new(ASTNodeType::ListExpression(points), span))
+}
+
+//number of tokens in the name ( variable ) = that begins a definition
+const DEFINITION_HEAD_LENGTH: usize = 5;
+
+//function to get the name of the function an input defines, if it begins with name ( variable ) =
+pub fn definition_name(tokens: &[Token]) -> Option<String> {
+    if tokens.len() < DEFINITION_HEAD_LENGTH { return None; }
+    let head: Vec<&TokenType> = tokens[..DEFINITION_HEAD_LENGTH].iter().map(|token| &token.token_type).collect();
+    match head[..] {
+        [TokenType::Parameter(name) | TokenType::UserFunction(name), TokenType::LeftParen, TokenType::Variable(_), TokenType::RightParen, 
+         TokenType::Equals] => Some(name.clone()),
+        _ => None
+    }
+}
+
+//function to check whether a token is a comparison operator
+fn is_comparison(token: &TokenType) -> bool {
+    matches!(token, TokenType::Less | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual)
 }
 
-//main parsing function - takes vector of tokens and produced AST
-pub fn parse(tokens: &Vec<TokenType>) -> Result<ASTNodeType, String> {
-    let expr = parse_expression(tokens, 0);
+//function to parse the tokens from start to the end of input as a single expression
+fn parse_from(tokens: &[Token], start: usize) -> Result<ASTNode, ParseError> {
+    let expr = parse_expression(tokens, start);
     return_if_error_or_unwrap!(expr, stop);
-    if stop != tokens.len() { return Err(String::from("Error: failed to parse expression")); }
+    expect_end_of_input(tokens, stop)?;
     Ok(expr)
 }
 
+//function to check that parsing stopped at the end of input, and produce an error for the token it stopped at otherwise
+fn expect_end_of_input(tokens: &[Token], stop: usize) -> Result<(), ParseError> {
+    if stop != tokens.len() { 
+        if tokens[stop].token_type == TokenType::RightParen { return Err(ParseError::UnbalancedParenthesis { span: tokens[stop].span }); }
+        return Err(ParseError::UnexpectedToken { expected: String::from("end of input"), found: tokens[stop].token_type.to_string(), span: tokens[stop].span }); 
+    }
+    Ok(())
+}
+
 //function to parse an expression and produce an AST node
-fn parse_expression(tokens: &Vec<TokenType>, start: usize) -> Result<(ASTNodeType, usize), String> {
+fn parse_expression(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
     //no empty expressions allowed
     expect_more_tokens!(tokens, start);
     let left = parse_term(tokens, start);
@@ -60,54 +355,83 @@ fn parse_expression(tokens: &Vec<TokenType>, start: usize) -> Result<(ASTNodeTyp
     let ( mut left, mut next ) = left.unwrap();
 
     //as long as we see a + or - next in token stream, continue to parse expression, and ensure left associativity
-    while next < tokens.len() && (tokens[next] == TokenType::Add || tokens[next] == TokenType::Sub) {
-        let operator = tokens[next].clone();
+    while next < tokens.len() && (tokens[next].token_type == TokenType::Add || tokens[next].token_type == TokenType::Sub) {
+        let operator = tokens[next].token_type.clone();
         let right = parse_term(tokens, next + 1);
         if let Err(e) = right { return Err(e); }
         let right_unwrapped = right.unwrap();
         let right = right_unwrapped.0;
         next = right_unwrapped.1;
-        left = ASTNodeType::BinaryExpression(operator, Box::new(left), Box::new(right));
+        let span = left.span.to(right.span);
+        left = ASTNode::new(ASTNodeType::BinaryExpression(operator, Box::new(left), Box::new(right)), span);
     }
 
     Ok((left, next))
 }
 
 //function to parse term and produce an AST node
-fn parse_term(tokens: &Vec<TokenType>, start: usize) -> Result<(ASTNodeType, usize), String> {
+fn parse_term(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
     expect_more_tokens!(tokens, start);
-    let left = parse_unary(tokens, start);
+    let left = parse_implicit(tokens, start);
     if let Err(e) = left { return Err(e); }
     let ( mut left, mut next ) = left.unwrap();
 
-    //same procedure as for expressions, but check for * or /
-    while next < tokens.len() && (tokens[next] == TokenType::Mul || tokens[next] == TokenType::Div) {
-        let operator = tokens[next].clone();
-        let right = parse_unary(tokens, next + 1);
+    //same procedure as for expressions, but check for *, / or mod
+    while next < tokens.len() && matches!(tokens[next].token_type, TokenType::Mul | TokenType::Div | TokenType::Mod) {
+        let operator = tokens[next].token_type.clone();
+        let right = parse_implicit(tokens, next + 1);
         if let Err(e) = right { return Err(e); }
         let right_unwrapped = right.unwrap();
         let right = right_unwrapped.0;
         next = right_unwrapped.1;
-        if operator == TokenType::Div {
-            //make division by 0 a syntax error
-            if let ASTNodeType::AtomicExpression(TokenType::NumLiteral(num)) = right {
-                if num == 0.0 { return Err(String::from("Error: attempted division by 0")); }
+        if operator == TokenType::Div || operator == TokenType::Mod {
+            //make division (or modulo) by 0 a syntax error
+            if let ASTNodeType::AtomicExpression(TokenType::NumLiteral(num)) = right.node_type {
+                if num == 0.0 { return Err(ParseError::DivisionByZero { span: right.span }); }
             }
         }
-        left = ASTNodeType::BinaryExpression(operator, Box::new(left), Box::new(right));
+        let span = left.span.to(right.span);
+        left = ASTNode::new(ASTNodeType::BinaryExpression(operator, Box::new(left), Box::new(right)), span);
+    }
+
+    Ok((left, next))
+}
+
+//function to parse a run of implicitly multiplied operands and produce an AST node - only the first operand may be negated
+fn parse_implicit(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
+    expect_more_tokens!(tokens, start);
+    let left = parse_unary(tokens, start);
+    if let Err(e) = left { return Err(e); }
+    let ( mut left, mut next ) = left.unwrap();
+
+    //as long as the next token starts a new operand, multiply it onto the left side, ensuring left associativity
+    while next < tokens.len() && starts_operand(&tokens[next].token_type) {
+        let right = parse_power(tokens, next);
+        return_if_error_or_unwrap!(right, after);
+        next = after;
+        let span = left.span.to(right.span);
+        left = ASTNode::new(ASTNodeType::BinaryExpression(TokenType::Mul, Box::new(left), Box::new(right)), span);
     }
 
     Ok((left, next))
 }
 
+//function to check whether a token can begin an operand, and so whether it triggers implicit multiplication
+fn starts_operand(token: &TokenType) -> bool {
+    matches!(token, TokenType::NumLiteral(_) | TokenType::Constant(_) | TokenType::Variable(_) | TokenType::Parameter(_) | TokenType::FunctionName(_) 
+                     | TokenType::UserFunction(_) | TokenType::If | TokenType::Radical | TokenType::LeftParen | TokenType::LeftPipe 
+                     | TokenType::LeftBrace)
+}
+
 //function to parse a unary expression and produce an AST node
-fn parse_unary(tokens: &Vec<TokenType>, start: usize) -> Result<(ASTNodeType, usize), String> {
+fn parse_unary(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
     expect_more_tokens!(tokens, start);
-    if tokens[start] == TokenType::Sub {
-        let operator = tokens[start].clone();
+    if tokens[start].token_type == TokenType::Sub {
+        let operator = tokens[start].token_type.clone();
         let expr = parse_unary(tokens, start + 1);
         return_if_error_or_unwrap!(expr, next);
-        let unary = ASTNodeType::UnaryExpression(operator, Box::new(expr));
+        let span = tokens[start].span.to(expr.span);
+        let unary = ASTNode::new(ASTNodeType::UnaryExpression(operator, Box::new(expr)), span);
         return Ok((unary, next));
     }
     let power = parse_power(tokens, start);
@@ -116,52 +440,245 @@ fn parse_unary(tokens: &Vec<TokenType>, start: usize) -> Result<(ASTNodeType, us
 }
 
 //function to parse a power expression and produce an AST node, ensuring right associativity
-fn parse_power(tokens: &Vec<TokenType>, start: usize) -> Result<(ASTNodeType, usize), String> {
+fn parse_power(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
     expect_more_tokens!(tokens, start);
-    let base = parse_function(tokens, start);
+    let base = parse_postfix(tokens, start);
     return_if_error_or_unwrap!(base, next);
-    if next < tokens.len() && tokens[next] == TokenType::Exp {
-        let operator = tokens[next].clone();
-        let power = parse_power(tokens, next + 1);
+    if next < tokens.len() && tokens[next].token_type == TokenType::Exp {
+        let operator = tokens[next].token_type.clone();
+        let power = parse_exponent(tokens, next + 1);
         return_if_error_or_unwrap!(power, next);
-        let power = ASTNodeType::BinaryExpression(operator, Box::new(base), Box::new(power));
+        let span = base.span.to(power.span);
+        let power = ASTNode::new(ASTNodeType::BinaryExpression(operator, Box::new(base), Box::new(power)), span);
         return Ok((power, next));
     }
     Ok((base, next))
 }
 
+//function to parse a function followed by any number of postfix factorials and produce an AST node
+fn parse_postfix(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
+    expect_more_tokens!(tokens, start);
+    let expr = parse_function(tokens, start);
+    if let Err(e) = expr { return Err(e); }
+    let ( mut expr, mut next ) = expr.unwrap();
+    while next < tokens.len() && tokens[next].token_type == TokenType::Factorial {
+        let span = expr.span.to(tokens[next].span);
+        expr = ASTNode::new(ASTNodeType::UnaryExpression(TokenType::Factorial, Box::new(expr)), span);
+        next += 1;
+    }
+    Ok((expr, next))
+}
+
+//function to parse the exponent of a power expression, which may be negated (x^-1), and produce an AST node
+fn parse_exponent(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
+    expect_more_tokens!(tokens, start);
+    if tokens[start].token_type == TokenType::Sub {
+        let operator = tokens[start].token_type.clone();
+        let expr = parse_exponent(tokens, start + 1);
+        return_if_error_or_unwrap!(expr, next);
+        let span = tokens[start].span.to(expr.span);
+        let unary = ASTNode::new(ASTNodeType::UnaryExpression(operator, Box::new(expr)), span);
+        return Ok((unary, next));
+    }
+    parse_power(tokens, start)
+}
+
 //function to parse function and produce AST node
-fn parse_function(tokens: &Vec<TokenType>, start: usize) -> Result<(ASTNodeType, usize), String> {
+fn parse_function(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
     expect_more_tokens!(tokens, start);
-    if let TokenType::FunctionName(_) = &tokens[start] {
-        let name = tokens[start].clone();
+    //√ is a prefix square root that takes the power expression after it as its argument
+    if tokens[start].token_type == TokenType::Radical {
+        let argument = parse_power(tokens, start + 1);
+        return_if_error_or_unwrap!(argument, next);
+        let span = tokens[start].span.to(argument.span);
+        let function = ASTNode::new(ASTNodeType::FunctionExpression(TokenType::FunctionName(String::from("sqrt")), vec![argument]), span);
+        return Ok((function, next));
+    }
+    if let TokenType::FunctionName(function_name) | TokenType::UserFunction(function_name) = &tokens[start].token_type {
+        let name = tokens[start].token_type.clone();
         expect_token!(tokens, start + 1, TokenType::LeftParen);
         let expression = parse_expression(tokens, start + 2);
         return_if_error_or_unwrap!(expression, next);
-        expect_token!(tokens, next, TokenType::RightParen);
-        let function = ASTNodeType::FunctionExpression(name, Box::new(expression));
+        let mut arguments = vec![expression];
+        let mut next = next;
+        //parse any further comma separated arguments
+        while next < tokens.len() && tokens[next].token_type == TokenType::Comma {
+            let expression = parse_expression(tokens, next + 1);
+            return_if_error_or_unwrap!(expression, after);
+            arguments.push(expression);
+            next = after;
+        }
+        expect_closing!(tokens, next, TokenType::RightParen, ParseError::UnbalancedParenthesis { span: tokens[start + 1].span });
+        let span = tokens[start].span.to(tokens[next].span);
+        let (min, max) = function_arity(function_name);
+        if arguments.len() < min || max.is_some_and(|max| arguments.len() > max) {
+            return Err(ParseError::WrongArgumentCount { function: function_name.clone(), min, max, found: arguments.len(), span });
+        }
+        let function = ASTNode::new(ASTNodeType::FunctionExpression(name, arguments), span);
         return Ok((function, next + 1));
     }
+    //if(condition, a, b) is a piecewise expression that is a where the condition holds and b elsewhere, or undefined if b is left out
+    if tokens[start].token_type == TokenType::If {
+        expect_token!(tokens, start + 1, TokenType::LeftParen);
+        let condition = parse_condition(tokens, start + 2);
+        return_if_error_or_unwrap!(condition, next);
+        let mut values = Vec::new();
+        let mut next = next;
+        while next < tokens.len() && tokens[next].token_type == TokenType::Comma {
+            let expression = parse_expression(tokens, next + 1);
+            return_if_error_or_unwrap!(expression, after);
+            values.push(expression);
+            next = after;
+        }
+        expect_closing!(tokens, next, TokenType::RightParen, ParseError::UnbalancedParenthesis { span: tokens[start + 1].span });
+        let span = tokens[start].span.to(tokens[next].span);
+        if values.is_empty() || values.len() > 2 {
+            return Err(ParseError::WrongArgumentCount { function: String::from("if"), min: 2, max: Some(3), found: values.len() + 1, span });
+        }
+        let default = if values.len() == 2 { values.pop().map(Box::new) } else { None };
+        let value = values.pop().unwrap();
+        return Ok((ASTNode::new(ASTNodeType::PiecewiseExpression(vec![(condition, value)], default), span), next + 1));
+    }
     let atom = parse_atom(tokens, start);
     return_if_error_or_unwrap!(atom, next);
     Ok((atom, next))
 }
 
 //function to parse atomic expression and produce AST node
-fn parse_atom(tokens: &Vec<TokenType>, start: usize) -> Result<(ASTNodeType, usize), String> {
+fn parse_atom(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
     expect_more_tokens!(tokens, start);
-    match &tokens[start] {
-        TokenType::NumLiteral(_) | TokenType::Constant(_) | TokenType::Variable(_) => {
-            Ok((ASTNodeType::AtomicExpression(tokens[start].clone()), start + 1))
+    match &tokens[start].token_type {
+        TokenType::NumLiteral(_) | TokenType::Constant(_) | TokenType::Variable(_) | TokenType::Parameter(_) => {
+            Ok((ASTNode::new(ASTNodeType::AtomicExpression(tokens[start].token_type.clone()), tokens[start].span), start + 1))
         }
         TokenType::LeftParen => {
             let expression = parse_expression(tokens, start + 1);
             return_if_error_or_unwrap!(expression, next);
-            expect_token!(tokens, next, TokenType::RightParen);
-            Ok((expression, next+1))
+            //a second expression after a comma makes a point
+            if next < tokens.len() && tokens[next].token_type == TokenType::Comma {
+                let y = parse_expression(tokens, next + 1);
+                return_if_error_or_unwrap!(y, after);
+                expect_closing!(tokens, after, TokenType::RightParen, ParseError::UnbalancedParenthesis { span: tokens[start].span });
+                let span = tokens[start].span.to(tokens[after].span);
+                return Ok((ASTNode::new(ASTNodeType::PointExpression(Box::new(expression), Box::new(y)), span), after + 1));
+            }
+            expect_closing!(tokens, next, TokenType::RightParen, ParseError::UnbalancedParenthesis { span: tokens[start].span });
+            //a parenthesized expression's span includes its parentheses
+            let span = tokens[start].span.to(tokens[next].span);
+            Ok((ASTNode::new(expression.node_type, span), next+1))
+        }
+        TokenType::LeftPipe => {
+            let unbalanced = ParseError::UnbalancedAbsoluteValue { span: tokens[start].span };
+            if start + 1 >= tokens.len() { return Err(unbalanced); }
+            let expression = parse_expression(tokens, start + 1);
+            return_if_error_or_unwrap!(expression, next);
+            expect_closing!(tokens, next, TokenType::RightPipe, unbalanced);
+            let span = tokens[start].span.to(tokens[next].span);
+            Ok((ASTNode::new(ASTNodeType::FunctionExpression(TokenType::FunctionName(String::from("abs")), vec![expression]), span), next + 1))
+        }
+        TokenType::LeftBrace => parse_piecewise(tokens, start),
+        other => Err(ParseError::ExpectedExpression { found: other.to_string(), span: tokens[start].span })
+    }
+}
+
+/*function to parse a piecewise expression starting at the { at start and produce an AST node. Branches are separated by commas, and each
+is a condition with a value (x < 0: -x), a condition alone, whose value is 1 so that x^2 {x < 0} is x^2 restricted to x < 0, or, as the
+last branch only, a value alone that is used where no condition holds*/
+fn parse_piecewise(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
+    let mut branches = Vec::new();
+    let mut default = None;
+    let mut next = start + 1;
+    loop {
+        let expression = parse_expression(tokens, next);
+        return_if_error_or_unwrap!(expression, after);
+        if after < tokens.len() && is_comparison(&tokens[after].token_type) {
+            //the expression began a condition, so parse the branch again from its start
+            let condition = parse_condition(tokens, next);
+            return_if_error_or_unwrap!(condition, after);
+            next = after;
+            let value = if next < tokens.len() && tokens[next].token_type == TokenType::Colon {
+                let value = parse_expression(tokens, next + 1);
+                return_if_error_or_unwrap!(value, after);
+                next = after;
+                value
+            }
+            else { ASTNode::new(ASTNodeType::AtomicExpression(TokenType::NumLiteral(1.0)), condition.span) };
+            branches.push((condition, value));
         }
-        other => Err(format!("Error: expected expression, found {}", other))
+        else {
+            if after < tokens.len() && tokens[after].token_type == TokenType::Colon {
+                return Err(ParseError::ExpectedCondition { span: expression.span });
+            }
+            next = after;
+            default = Some(Box::new(expression));
+        }
+        if default.is_some() || next >= tokens.len() || tokens[next].token_type != TokenType::Comma { break; }
+        next += 1;
+    }
+    expect_closing!(tokens, next, TokenType::RightBrace, ParseError::UnbalancedBrace { span: tokens[start].span });
+    let span = tokens[start].span.to(tokens[next].span);
+    Ok((ASTNode::new(ASTNodeType::PiecewiseExpression(branches, default), span), next + 1))
+}
+
+//function to parse a condition - comparisons joined by and and or, where and binds more tightly - and produce an AST node
+fn parse_condition(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
+    expect_more_tokens!(tokens, start);
+    let left = parse_conjunction(tokens, start);
+    if let Err(e) = left { return Err(e); }
+    let ( mut left, mut next ) = left.unwrap();
+    while next < tokens.len() && tokens[next].token_type == TokenType::Or {
+        let right = parse_conjunction(tokens, next + 1);
+        return_if_error_or_unwrap!(right, after);
+        next = after;
+        let span = left.span.to(right.span);
+        left = ASTNode::new(ASTNodeType::LogicalExpression(TokenType::Or, Box::new(left), Box::new(right)), span);
+    }
+    Ok((left, next))
+}
+
+//function to parse comparisons joined by and and produce an AST node
+fn parse_conjunction(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
+    expect_more_tokens!(tokens, start);
+    let left = parse_comparison(tokens, start);
+    if let Err(e) = left { return Err(e); }
+    let ( mut left, mut next ) = left.unwrap();
+    while next < tokens.len() && tokens[next].token_type == TokenType::And {
+        let right = parse_comparison(tokens, next + 1);
+        return_if_error_or_unwrap!(right, after);
+        next = after;
+        let span = left.span.to(right.span);
+        left = ASTNode::new(ASTNodeType::LogicalExpression(TokenType::And, Box::new(left), Box::new(right)), span);
+    }
+    Ok((left, next))
+}
+
+//function to parse a comparison and produce an AST node - a chain of comparisons such as 0 < x < 1 holds where each comparison in it holds
+fn parse_comparison(tokens: &[Token], start: usize) -> Result<(ASTNode, usize), ParseError> {
+    expect_more_tokens!(tokens, start);
+    let left = parse_expression(tokens, start);
+    return_if_error_or_unwrap!(left, next);
+    if next >= tokens.len() || !is_comparison(&tokens[next].token_type) {
+        return Err(ParseError::ExpectedCondition { span: left.span });
+    }
+    let ( mut left, mut next ) = ( left, next );
+    let mut comparison: Option<ASTNode> = None;
+    while next < tokens.len() && is_comparison(&tokens[next].token_type) {
+        let operator = tokens[next].token_type.clone();
+        let right = parse_expression(tokens, next + 1);
+        return_if_error_or_unwrap!(right, after);
+        next = after;
+        let span = left.span.to(right.span);
+        let inequality = ASTNode::new(ASTNodeType::InequalityExpression(operator, Box::new(left), Box::new(right.clone())), span);
+        comparison = Some(match comparison {
+            Some(previous) => {
+                let span = previous.span.to(inequality.span);
+                ASTNode::new(ASTNodeType::LogicalExpression(TokenType::And, Box::new(previous), Box::new(inequality)), span)
+            }
+            None => inequality
+        });
+        left = right;
     }
+    Ok((comparison.unwrap(), next))
 }
 
 #[cfg(test)]
@@ -176,4 +693,216 @@ mod tests {
         let result = parse(&tokens);
         println!("{:?}", result);
     }
+
+    fn parse_to_string(expr: &str) -> String {
+        parse(&scan(expr).unwrap()).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_implicit_multiplication() {
+        assert_eq!(parse_to_string("2x"), "(2 * x)");
+        assert_eq!(parse_to_string("3sin(x)"), "(3 * sin(x))");
+        assert_eq!(parse_to_string("(x+1)(x-1)"), "((x + 1) * (x - 1))");
+        assert_eq!(parse_to_string("2pi"), "(2 * pi)");
+        assert_eq!(parse_to_string("sin(x)cos(x)"), "(sin(x) * cos(x))");
+        assert_eq!(parse_to_string("2 x pi"), "((2 * x) * pi)");
+    }
+
+    #[test]
+    fn test_implicit_multiplication_precedence() {
+        assert_eq!(parse_to_string("2x^2"), parse_to_string("2*(x^2)"));
+        assert_eq!(parse_to_string("1/2x"), parse_to_string("1/(2*x)"));
+        assert_eq!(parse_to_string("2x/3"), parse_to_string("(2*x)/3"));
+        assert_eq!(parse_to_string("x/2pi"), parse_to_string("x/(2*pi)"));
+        assert_eq!(parse_to_string("-2x"), parse_to_string("(-2)*x"));
+        assert_eq!(parse_to_string("2^3x"), parse_to_string("(2^3)*x"));
+        assert_eq!(parse_to_string("x^2pi"), parse_to_string("(x^2)*pi"));
+        assert_eq!(parse_to_string("2*3x"), parse_to_string("2*(3*x)"));
+    }
+
+    #[test]
+    fn test_subtraction_is_not_implicit_multiplication() {
+        assert_eq!(parse_to_string("x -1"), "(x - 1)");
+        assert_eq!(parse_to_string("2 - x"), "(2 - x)");
+    }
+
+    #[test]
+    fn test_node_spans() {
+        let ast = parse(&scan("2 * sin(x + 1)").unwrap()).unwrap();
+        assert_eq!(ast.span, Span::new(0, 14));
+        if let ASTNodeType::BinaryExpression(_, left, right) = ast.node_type {
+            assert_eq!(left.span, Span::new(0, 1));
+            assert_eq!(right.span, Span::new(4, 14));
+        }
+        else { panic!("expected binary expression"); }
+        let ast = parse(&scan("-(x)pi").unwrap()).unwrap();
+        assert_eq!(ast.span, Span::new(0, 6));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |expr: &str| parse(&scan(expr).unwrap()).unwrap_err();
+        let token = |expected: &str, found: &str, span: Span| ParseError::UnexpectedToken { expected: expected.to_string(), found: found.to_string(), span };
+        assert_eq!(error("sin x"), token("(", "x", Span::new(4, 5)));
+        assert_eq!(error("(x + 1"), ParseError::UnbalancedParenthesis { span: Span::new(0, 1) });
+        assert_eq!(error("2 * sin((x)"), ParseError::UnbalancedParenthesis { span: Span::new(7, 8) });
+        assert_eq!(error("x) + 1"), ParseError::UnbalancedParenthesis { span: Span::new(1, 2) });
+        assert_eq!(error("()"), ParseError::ExpectedExpression { found: String::from(")"), span: Span::new(1, 2) });
+        assert_eq!(error("x + "), ParseError::UnexpectedEndOfInput { span: Span::new(3, 3) });
+        assert_eq!(error(""), ParseError::UnexpectedEndOfInput { span: Span::new(0, 0) });
+        assert_eq!(error("x / 0"), ParseError::DivisionByZero { span: Span::new(4, 5) });
+        assert_eq!(error("2 * * x"), ParseError::ExpectedExpression { found: String::from("*"), span: Span::new(4, 5) });
+    }
+
+    #[test]
+    fn test_multiple_arguments() {
+        assert_eq!(parse_to_string("log(2, x)"), "log(2, x)");
+        assert_eq!(parse_to_string("max(x, 2x, x^2)"), "max(x, (2 * x), (x ^ 2))");
+        assert_eq!(parse_to_string("2atan2(x, 1)x"), "((2 * atan2(x, 1)) * x)");
+        assert_eq!(parse_to_string("root(3, mod(x, 3))"), "root(3, mod(x, 3))");
+    }
+
+    #[test]
+    fn test_argument_count() {
+        let error = |expr: &str| parse(&scan(expr).unwrap()).unwrap_err();
+        assert_eq!(error("sin(x, 2)"), ParseError::WrongArgumentCount { function: String::from("sin"), min: 1, max: Some(1), found: 2, span: Span::new(0, 9) });
+        assert_eq!(error("log(1, 2, x)"), ParseError::WrongArgumentCount { function: String::from("log"), min: 1, max: Some(2), found: 3, span: Span::new(0, 12) });
+        assert_eq!(error("max(x)"), ParseError::WrongArgumentCount { function: String::from("max"), min: 2, max: None, found: 1, span: Span::new(0, 6) });
+        assert_eq!(error("atan2(x,)"), ParseError::ExpectedExpression { found: String::from(")"), span: Span::new(8, 9) });
+        assert_eq!(error("x, 2"), ParseError::UnexpectedToken { expected: String::from("end of input"), found: String::from(","), span: Span::new(1, 2) });
+    }
+
+    #[test]
+    fn test_absolute_value() {
+        assert_eq!(parse_to_string("|x - 2|"), parse_to_string("abs(x - 2)"));
+        assert_eq!(parse_to_string("||x|-1|"), parse_to_string("abs(abs(x) - 1)"));
+        assert_eq!(parse_to_string("|x||pi|"), parse_to_string("abs(x) * abs(pi)"));
+        assert_eq!(parse_to_string("2|x|^2"), parse_to_string("2 * abs(x)^2"));
+        assert_eq!(parse_to_string("||x|*|x-1||"), parse_to_string("abs(abs(x) * abs(x - 1))"));
+        assert_eq!(parse_to_string("|-x|"), parse_to_string("abs(-x)"));
+        assert_eq!(parse(&scan("(|x|)").unwrap()).unwrap().span, Span::new(0, 5));
+    }
+
+    #[test]
+    fn test_unbalanced_absolute_value() {
+        let error = |expr: &str| parse(&scan(expr).unwrap()).unwrap_err();
+        assert_eq!(error("|x"), ParseError::UnbalancedAbsoluteValue { span: Span::new(0, 1) });
+        assert_eq!(error("x|"), ParseError::UnbalancedAbsoluteValue { span: Span::new(1, 2) });
+        assert_eq!(error("|x|2|"), ParseError::UnbalancedAbsoluteValue { span: Span::new(4, 5) });
+        assert_eq!(error("|(x|)"), ParseError::UnexpectedToken { expected: String::from(")"), found: String::from("|"), span: Span::new(3, 4) });
+    }
+
+    #[test]
+    fn test_parameters() {
+        assert_eq!(parse_to_string("a*sin(b*x + c)"), "(a * sin(((b * x) + c)))");
+        assert_eq!(parse_to_string("ax^2 + bx + c"), "(((a * (x ^ 2)) + (b * x)) + c)");
+        assert_eq!(parse_to_string("k(x - h)"), "(k * (x - h))");
+    }
+
+    #[test]
+    fn test_definitions() {
+        let tokens = scan("g(x) = x^2 + 1").unwrap();
+        assert_eq!(definition_name(&tokens), Some(String::from("g")));
+        let Statement::Definition(name, span, parameter, expression) = parse_statement(&tokens).unwrap() else { panic!("expected definition"); };
+        assert_eq!((name.as_str(), span, parameter.as_str()), ("g", Span::new(0, 1), "x"));
+        assert_eq!(expression.to_string(), "((x ^ 2) + 1)");
+        //the definition is echoed with the parameter it was written with
+        assert_eq!(parse_statement(&scan("h(y) = 2y").unwrap()).unwrap().to_string(), "h(y) = (2 * y)");
+        assert!(matches!(parse_statement(&scan("a(x)").unwrap()), Ok(Statement::Expression(_))));
+        assert_eq!(parse_to_string("2f1(x)"), "(2 * f1(x))");
+        assert!(matches!(parse(&scan("f1(x, 2)").unwrap()), Err(ParseError::WrongArgumentCount { .. })));
+    }
+
+    #[test]
+    fn test_equations() {
+        let statement = |input: &str| parse_statement(&scan(input).unwrap());
+        assert_eq!(statement("y = 2x + 1").unwrap().to_string(), "y = ((2 * x) + 1)");
+        assert_eq!(statement("x = 3").unwrap().to_string(), "x = 3");
+        assert!(matches!(statement("x^2 = y"), Ok(Statement::Equation(_, _))));
+        assert_eq!(statement("y ="), Err(ParseError::UnexpectedEndOfInput { span: Span::new(3, 3) }));
+        assert!(matches!(statement("y = x = 2"), Err(ParseError::UnexpectedToken { .. })));
+        //= is only allowed in a whole input
+        assert!(matches!(statement("sin(y = x)"), Err(ParseError::UnexpectedToken { .. })));
+    }
+
+    #[test]
+    fn test_inequalities() {
+        let statement = |input: &str| parse_statement(&scan(input).unwrap());
+        assert_eq!(statement("y < x^2").unwrap().to_string(), "y < (x ^ 2)");
+        assert_eq!(statement("x^2 + y^2 >= 4").unwrap().to_string(), "((x ^ 2) + (y ^ 2)) >= 4");
+        assert!(matches!(statement("y < 2 < x"), Err(ParseError::UnexpectedToken { .. })));
+        assert_eq!(statement("y <"), Err(ParseError::UnexpectedEndOfInput { span: Span::new(3, 3) }));
+    }
+
+    #[test]
+    fn test_piecewise() {
+        assert_eq!(parse_to_string("{x < 0: -x, x >= 0: sqrt(x)}"), "{x < 0: (-x), x >= 0: sqrt(x)}");
+        assert_eq!(parse_to_string("{x < 0: 1, 2x}"), "{x < 0: 1, (2 * x)}");
+        assert_eq!(parse_to_string("x^2 {x < 0}"), "((x ^ 2) * {x < 0: 1})");
+        assert_eq!(parse_to_string("if(x > 1, 1/x, x)"), "{x > 1: (1 / x), x}");
+        assert_eq!(parse_to_string("if(x > 1, x)"), "{x > 1: x}");
+        assert_eq!(parse_to_string("{0 < x < 1 or x > 2 and x < 3: x}"), "{((0 < x and x < 1) or (x > 2 and x < 3)): x}");
+        assert_eq!(parse(&scan("{x < 0: x").unwrap()), Err(ParseError::UnbalancedBrace { span: Span::new(0, 1) }));
+        assert_eq!(parse(&scan("{x: 1}").unwrap()), Err(ParseError::ExpectedCondition { span: Span::new(1, 2) }));
+        assert_eq!(parse(&scan("if(x, 1)").unwrap()), Err(ParseError::ExpectedCondition { span: Span::new(3, 4) }));
+        assert!(matches!(parse(&scan("if(x > 0)").unwrap()), Err(ParseError::WrongArgumentCount { found: 1, .. })));
+        //the default branch must come last
+        assert!(matches!(parse(&scan("{1, x < 0: 2}").unwrap()), Err(ParseError::UnexpectedToken { .. })));
+    }
+
+    #[test]
+    fn test_points() {
+        let statement = |input: &str| parse_statement(&scan(input).unwrap());
+        assert_eq!(statement("(1, 2.1), (2, 3.9), (3, 6.2)").unwrap().to_string(), "(1, 2.1), (2, 3.9), (3, 6.2)");
+        assert_eq!(statement("(a, -a/2)").unwrap().to_string(), "(a, ((-a) / 2))");
+        assert_eq!(statement("(1, 2), 3"), Err(ParseError::ExpectedPoint { span: Span::new(8, 9) }));
+        assert_eq!(statement("(1, 2"), Err(ParseError::UnbalancedParenthesis { span: Span::new(0, 1) }));
+        //points inside other expressions are parsed, and rejected when instructions are generated
+        assert_eq!(parse_to_string("2(1, 2)"), "(2 * (1, 2))");
+        assert_eq!(statement("(1, 2) + 1").unwrap().to_string(), "((1, 2) + 1)");
+    }
+
+    #[test]
+    fn test_factorial() {
+        assert_eq!(parse_to_string("x!"), "(x!)");
+        assert_eq!(parse_to_string("-x!"), "(-(x!))");
+        assert_eq!(parse_to_string("2^x!"), "(2 ^ (x!))");
+        assert_eq!(parse_to_string("x!^2"), "((x!) ^ 2)");
+        assert_eq!(parse_to_string("2x!"), "(2 * (x!))");
+        assert_eq!(parse_to_string("x!!"), "((x!)!)");
+        assert_eq!(parse_to_string("(x+1)!"), "((x + 1)!)");
+        assert_eq!(parse_to_string("|x|!"), "(abs(x)!)");
+    }
+
+    #[test]
+    fn test_mod() {
+        assert_eq!(parse_to_string("x mod 3"), "(x mod 3)");
+        assert_eq!(parse_to_string("x % 3"), "(x mod 3)");
+        assert_eq!(parse_to_string("2x mod 3 + 1"), "(((2 * x) mod 3) + 1)");
+        assert_eq!(parse_to_string("x mod 2 * 3"), "((x mod 2) * 3)");
+        assert_eq!(parse_to_string("mod(x, 3)"), "mod(x, 3)");
+        assert_eq!(parse(&scan("x mod 0").unwrap()).unwrap_err(), ParseError::DivisionByZero { span: Span::new(6, 7) });
+    }
+
+    #[test]
+    fn test_negative_exponent() {
+        assert_eq!(parse_to_string("x^-1"), "(x ^ (-1))");
+        assert_eq!(parse_to_string("2^-x^2"), "(2 ^ (-(x ^ 2)))");
+    }
+
+    #[test]
+    fn test_unicode_matches_ascii() {
+        let pairs = [("2×x", "2*x"), ("x·π", "x*pi"), ("2π", "2pi"), ("x÷2", "x/2"), ("3−x", "3-x"), ("x²", "x^2"), ("2x³", "2x^3"), 
+                     ("x⁻¹", "x^-1"), ("√x", "sqrt(x)"), ("√(x+1)", "sqrt(x+1)"), ("2√x", "2sqrt(x)"), ("√x^2", "sqrt(x^2)"), 
+                     ("√2x", "sqrt(2)x"), ("√√x", "sqrt(sqrt(x))")];
+        for (unicode, ascii) in pairs {
+            assert_eq!(parse_to_string(unicode), parse_to_string(ascii), "{} should parse like {}", unicode, ascii);
+        }
+    }
+
+    #[test]
+    fn test_implicit_multiplication_errors() {
+        assert!(parse(&scan("2x)").unwrap()).is_err());
+        assert!(parse(&scan("(x+1)(").unwrap()).is_err());
+    }
 }
\ No newline at end of file
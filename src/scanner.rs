@@ -1,14 +1,25 @@
 use std::fmt;
+use serde::Serialize;
 
-const RECOGNIZED_FUNCTIONS: &[& str] = &["sin", "cos", "tan", "log", "ln", "sqrt", "abs"];
+use crate::error::ScanError;
+
+pub const RECOGNIZED_FUNCTIONS: &[& str] = &["sin", "cos", "tan", "log", "ln", "sqrt", "abs", "asin", "acos", "atan", "sec", "csc", "cot", 
+                                             "sinh", "cosh", "tanh", "asinh", "acosh", "atanh", "exp", "floor", "ceil", "round", "sign", "cbrt",
+                                             "max", "min", "atan2", "root", "mod"];
+//number of arguments accepted by each function that doesn't take exactly one, as (minimum, maximum) - a maximum of None means no limit
+const FUNCTION_ARITIES: &[(& str, usize, Option<usize>)] = &[("log", 1, Some(2)), ("max", 2, None), ("min", 2, None), ("atan2", 2, Some(2)),
+                                                              ("root", 2, Some(2)), ("mod", 2, Some(2))];
 const RECOGNIZED_CONSTANTS: &[& str] = &["e", "pi"];
-const RECOGNIZED_VARIABLES: &[& str] = &["x"];
+//theta (or θ) is the angle of a polar function, and is sampled like x
+pub const RECOGNIZED_VARIABLES: &[& str] = &["x", "y", "theta"];
 
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum TokenType {
     Variable(String),
+    Parameter(String),
     FunctionName(String),
+    UserFunction(String),
     NumLiteral(f64),
     Constant(String),
     LeftParen,
@@ -17,13 +28,69 @@ pub enum TokenType {
     Div,
     Exp,
     Sub,
-    Add
+    Add,
+    Radical,
+    Comma,
+    LeftPipe,
+    RightPipe,
+    Factorial,
+    Mod,
+    Equals,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+    If,
+    LeftBrace,
+    RightBrace,
+    Colon
+}
+
+//range of character positions [start, end) in the input string that a token, AST node or error refers to
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    //function to produce the smallest span covering both this span and another span that comes after it
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub span: Span
+}
+
+impl Token {
+    pub fn new(token_type: TokenType, span: Span) -> Token {
+        Token { token_type, span }
+    }
+}
+
+//function to get the (minimum, maximum) number of arguments a recognized function accepts
+pub fn function_arity(name: &str) -> (usize, Option<usize>) {
+    match FUNCTION_ARITIES.iter().find(|(function, _, _)| *function == name) {
+        Some((_, min, max)) => (*min, *max),
+        None => (1, Some(1))
+    }
 }
 
 impl fmt::Display for TokenType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            &TokenType::Variable(s) | &TokenType::FunctionName(s) | &TokenType::Constant(s) => write!(f, "{}", s),
+            &TokenType::Variable(s) | &TokenType::Parameter(s) | &TokenType::FunctionName(s) | &TokenType::UserFunction(s)
+            | &TokenType::Constant(s) => write!(f, "{}", s),
             &TokenType::NumLiteral(num) => write!(f, "{}", num),
             &TokenType::LeftParen => write!(f, "("),
             &TokenType::RightParen => write!(f, ")"),
@@ -31,14 +98,30 @@ impl fmt::Display for TokenType {
             &TokenType::Div => write!(f, "/"),
             &TokenType::Exp => write!(f, "^"),
             &TokenType::Sub => write!(f, "-"),
-            &TokenType::Add => write!(f, "+")
+            &TokenType::Add => write!(f, "+"),
+            &TokenType::Radical => write!(f, "√"),
+            &TokenType::Comma => write!(f, ","),
+            &TokenType::LeftPipe | &TokenType::RightPipe => write!(f, "|"),
+            &TokenType::Factorial => write!(f, "!"),
+            &TokenType::Mod => write!(f, "mod"),
+            &TokenType::Equals => write!(f, "="),
+            &TokenType::Less => write!(f, "<"),
+            &TokenType::LessEqual => write!(f, "<="),
+            &TokenType::Greater => write!(f, ">"),
+            &TokenType::GreaterEqual => write!(f, ">="),
+            &TokenType::And => write!(f, "and"),
+            &TokenType::Or => write!(f, "or"),
+            &TokenType::If => write!(f, "if"),
+            &TokenType::LeftBrace => write!(f, "{{"),
+            &TokenType::RightBrace => write!(f, "}}"),
+            &TokenType::Colon => write!(f, ":")
         }
     }
 }
 
 
-//main scanning function - takes string and produces vector of tokens
-pub fn scan(input_string: &String) -> Result<Vec<TokenType>, String> {
+//main scanning function - takes string and produces vector of tokens, each with the span of characters it was scanned from
+pub fn scan(input_string: &str) -> Result<Vec<Token>, ScanError> {
     
     let mut tokens = Vec::new();
     let chars : Vec<char>  = input_string.chars().collect();
@@ -48,19 +131,32 @@ pub fn scan(input_string: &String) -> Result<Vec<TokenType>, String> {
         let c  = chars[i];
         
         //skip whitespace
-        if c.is_whitespace() { i = i + 1; continue; }
-
-        if c == '(' { tokens.push(TokenType::LeftParen); }
-        else if c == ')' { tokens.push(TokenType::RightParen); }
-        else if c == '*' { tokens.push(TokenType::Mul); }
-        else if c == '/' { tokens.push(TokenType::Div); }
-        else if c == '^' { tokens.push(TokenType::Exp); }
-        else if c == '-' { tokens.push(TokenType::Sub); }
-        else if c == '+' { tokens.push(TokenType::Add); }
-        else if c.is_numeric() {
+        if c.is_whitespace() { i += 1; continue; }
+
+        if c == '|' { 
+            let token_type = classify_pipe(&tokens);
+            tokens.push(Token::new(token_type, Span::new(i, i + 1))); 
+        }
+        else if let Some((token_type, resume_idx)) = scan_comparison(&chars, i) {
+            tokens.push(Token::new(token_type, Span::new(i, resume_idx)));
+            i = resume_idx;
+            continue;
+        }
+        else if let Some(token_type) = scan_symbol(c) { tokens.push(Token::new(token_type, Span::new(i, i + 1))); }
+        else if superscript_value(c).is_some() || c == '⁻' {
+            match scan_superscript(&chars, i) {
+                Ok((mut superscript_tokens, resume_idx)) => {
+                    tokens.append(&mut superscript_tokens);
+                    i = resume_idx;
+                    continue;
+                }
+                Err(e) => { return Err(e); }
+            }
+        }
+        else if c.is_ascii_digit() {
             match scan_number(&chars, i) {
                 Ok((token, resume_idx)) => {
-                    tokens.push(token);
+                    tokens.push(Token::new(token, Span::new(i, resume_idx)));
                     i = resume_idx;
                     continue;
                 }
@@ -70,7 +166,7 @@ pub fn scan(input_string: &String) -> Result<Vec<TokenType>, String> {
         else if c.is_alphabetic() {
             match scan_letters(&chars, i) {
                 Ok((token, resume_idx)) => {
-                    tokens.push(token);
+                    tokens.push(Token::new(token, Span::new(i, resume_idx)));
                     i = resume_idx;
                     continue;
                 }
@@ -78,76 +174,279 @@ pub fn scan(input_string: &String) -> Result<Vec<TokenType>, String> {
             }
         }
         else {
-            return Err(format!("Error: invalid character '{c}' found while scanning input"))
+            return Err(ScanError::UnexpectedCharacter { character: c, span: Span::new(i, i + 1) })
         }
 
-        i = i + 1;
+        i += 1;
     }
 
+    classify_mod(&mut tokens);
     Ok(tokens)
 }
 
-//function to scan a number from a numeric string and produce a token containing the parsed floating point value
-fn scan_number(chars: &Vec<char>, start_idx: usize) -> Result<(TokenType, usize), String> {
+/*function to turn each mod that comes directly after an operand into the modulo operator, so x mod 3 is the same as x % 3 - unless it is 
+followed by a parenthesized list of two arguments, so that 2mod(x, 3) is still 2 * mod(x, 3)*/
+fn classify_mod(tokens: &mut [Token]) {
+    for i in 1..tokens.len() {
+        if tokens[i].token_type == TokenType::FunctionName(String::from("mod")) && ends_operand(&tokens[i - 1].token_type) 
+            && !starts_argument_list(tokens, i + 1) {
+            tokens[i].token_type = TokenType::Mod;
+        }
+    }
+}
+
+//function to check whether the tokens starting at start are a parenthesized list of more than one argument
+fn starts_argument_list(tokens: &[Token], start: usize) -> bool {
+    if start >= tokens.len() || tokens[start].token_type != TokenType::LeftParen { return false; }
+    let mut depth = 0;
+    for token in &tokens[start..] {
+        match token.token_type {
+            TokenType::LeftParen => depth += 1,
+            TokenType::RightParen => {
+                depth -= 1;
+                if depth == 0 { return false; }
+            }
+            TokenType::Comma if depth == 1 => return true,
+            _ => ()
+        }
+    }
+    false
+}
+
+/*function to decide whether a | opens or closes an absolute value, given the tokens scanned before it - a | closes the innermost open
+absolute value if it comes directly after the end of an operand, and opens a new one otherwise. So ||x|-1| is abs(abs(x) - 1), and 
+|x||y| is abs(x) * abs(y). Inside an absolute value, a | after an operand always closes, so a product of absolute values inside an 
+absolute value needs an explicit *, as in ||x|*|y||*/
+fn classify_pipe(tokens: &[Token]) -> TokenType {
+    let mut depth = 0;
+    for token in tokens {
+        match token.token_type {
+            TokenType::LeftPipe => depth += 1,
+            TokenType::RightPipe => depth -= 1,
+            _ => ()
+        }
+    }
+    let after_operand = tokens.last().is_some_and(|token| ends_operand(&token.token_type));
+    if depth > 0 && after_operand { TokenType::RightPipe } else { TokenType::LeftPipe }
+}
+
+/*function to turn each parameter that is called like a function into a call to the user defined function of the same name, if there is
+one - names holds the names defined by all inputs (see definition.rs), so that g(2x) calls g when some input defines g(x), and is 
+g * 2x otherwise*/
+pub fn mark_user_functions(tokens: &mut [Token], names: &[String]) {
+    for i in 0..tokens.len().saturating_sub(1) {
+        if let TokenType::Parameter(name) = &tokens[i].token_type {
+            if names.contains(name) && tokens[i + 1].token_type == TokenType::LeftParen {
+                tokens[i].token_type = TokenType::UserFunction(name.clone());
+            }
+        }
+    }
+}
+
+//function to turn each parameter with the given name into a variable, for expressions of a variable other than x and y (see definition.rs)
+pub fn mark_variable(tokens: &mut [Token], name: &str) {
+    for token in tokens.iter_mut() {
+        if token.token_type == TokenType::Parameter(String::from(name)) {
+            token.token_type = TokenType::Variable(String::from(name));
+        }
+    }
+}
+
+//function to check whether a token can be the last token of an operand
+fn ends_operand(token: &TokenType) -> bool {
+    matches!(token, TokenType::NumLiteral(_) | TokenType::Constant(_) | TokenType::Variable(_) | TokenType::Parameter(_) | TokenType::RightParen 
+                    | TokenType::RightPipe | TokenType::RightBrace | TokenType::Factorial)
+}
+
+//function to scan a comparison operator (<, <=, >, >=, or the unicode ≤ and ≥) starting at start_idx, if there is one
+fn scan_comparison(chars: &[char], start_idx: usize) -> Option<(TokenType, usize)> {
+    let or_equal = chars.get(start_idx + 1) == Some(&'=');
+    match chars[start_idx] {
+        '<' if or_equal => Some((TokenType::LessEqual, start_idx + 2)),
+        '>' if or_equal => Some((TokenType::GreaterEqual, start_idx + 2)),
+        '<' => Some((TokenType::Less, start_idx + 1)),
+        '>' => Some((TokenType::Greater, start_idx + 1)),
+        '≤' => Some((TokenType::LessEqual, start_idx + 1)),
+        '≥' => Some((TokenType::GreaterEqual, start_idx + 1)),
+        _ => None
+    }
+}
+
+//function to produce the token for a single character operator, parenthesis or symbol, if c is one - includes the unicode math symbols
+fn scan_symbol(c: char) -> Option<TokenType> {
+    match c {
+        '(' => Some(TokenType::LeftParen),
+        ')' => Some(TokenType::RightParen),
+        '*' | '×' | '·' | '∙' | '⋅' => Some(TokenType::Mul),
+        '/' | '÷' => Some(TokenType::Div),
+        '^' => Some(TokenType::Exp),
+        '-' | '−' => Some(TokenType::Sub),
+        '+' => Some(TokenType::Add),
+        '√' => Some(TokenType::Radical),
+        ',' => Some(TokenType::Comma),
+        '!' => Some(TokenType::Factorial),
+        '%' => Some(TokenType::Mod),
+        '=' => Some(TokenType::Equals),
+        '{' => Some(TokenType::LeftBrace),
+        '}' => Some(TokenType::RightBrace),
+        ':' => Some(TokenType::Colon),
+        '∧' => Some(TokenType::And),
+        '∨' => Some(TokenType::Or),
+        'π' => Some(TokenType::Constant(String::from("pi"))),
+        'θ' => Some(TokenType::Variable(String::from("theta"))),
+        _ => None
+    }
+}
+
+//function to get the digit a subscript digit character stands for, if c is one
+fn subscript_value(c: char) -> Option<char> {
+    match c {
+        '₀'..='₉' => char::from_digit(c as u32 - '₀' as u32, 10),
+        _ => None
+    }
+}
+
+//function to get the digit a superscript digit character stands for, if c is one
+fn superscript_value(c: char) -> Option<char> {
+    match c {
+        '⁰' => Some('0'),
+        '¹' => Some('1'),
+        '²' => Some('2'),
+        '³' => Some('3'),
+        '⁴' => Some('4'),
+        '⁵' => Some('5'),
+        '⁶' => Some('6'),
+        '⁷' => Some('7'),
+        '⁸' => Some('8'),
+        '⁹' => Some('9'),
+        _ => None
+    }
+}
+
+/*function to scan a run of superscript characters, optionally starting with a superscript minus, and produce the tokens for the 
+equivalent ascii exponent - x² scans the same as x^2 and x⁻¹ the same as x^-1. All tokens produced share the span of the run*/
+fn scan_superscript(chars: &[char], start_idx: usize) -> Result<(Vec<Token>, usize), ScanError> {
+    let mut i = start_idx;
+    let negative = chars[i] == '⁻';
+    if negative { i += 1; }
+    let mut digits = String::new();
+    while i < chars.len() {
+        match superscript_value(chars[i]) {
+            Some(digit) => digits.push(digit),
+            None => break
+        }
+        i += 1;
+    }
+    let span = Span::new(start_idx, i);
+    //a superscript minus must be followed by at least one superscript digit
+    if digits.is_empty() { return Err(ScanError::UnexpectedCharacter { character: chars[start_idx], span }); }
+    let mut tokens = vec![Token::new(TokenType::Exp, span)];
+    if negative { tokens.push(Token::new(TokenType::Sub, span)); }
+    tokens.push(Token::new(TokenType::NumLiteral(digits.parse::<f64>().unwrap()), span));
+    Ok((tokens, i))
+}
+
+/*function to scan a number from a numeric string and produce a token containing the parsed floating point value - numbers may be
+written in exponent form (1.5e-3, 6.02E23). An e or E directly after the digits of a number (no whitespace) followed by an optionally
+signed integer is always an exponent, so 2e-3 is 0.002 - to use the constant e there, write 2*e-3 or 2 e-3. An e that is not followed
+by an integer (2e, 2e+x, 2ex) is left for scan_letters and becomes the constant e, and an e that starts a word (e2) is always the constant*/
+fn scan_number(chars: &[char], start_idx: usize) -> Result<(TokenType, usize), ScanError> {
     let mut curr = String::new();
     let mut i = start_idx;
-    let decimal_found = false;
+    let mut decimal_found = false;
     while i < chars.len() {
         let c = chars[i];
-        if c.is_numeric() || c == '.' {
+        if c.is_ascii_digit() || c == '.' {
             //only allow number tokens to contain one decimal point
             if c == '.' && decimal_found {
-                return Err(String::from("Error: invalid number literal. Numbers may only contain one decimal point"));
+                let end = i + chars[i..].iter().take_while(|c| c.is_ascii_digit() || **c == '.').count();
+                return Err(ScanError::InvalidNumber { literal: chars[start_idx..end].iter().collect(), span: Span::new(start_idx, end) });
             }
+            if c == '.' { decimal_found = true; }
             curr.push(c);
-            i = i + 1;
+            i += 1;
         }
         else { break; }
     }
-    Ok((TokenType::NumLiteral(curr.parse::<f64>().unwrap()), i))
+    //check for an exponent, which must have at least one digit after the optional sign
+    if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+        let mut j = i + 1;
+        if j < chars.len() && (chars[j] == '+' || chars[j] == '-') { j += 1; }
+        if j < chars.len() && chars[j].is_ascii_digit() {
+            curr.extend(&chars[i..j]);
+            i = j;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                curr.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+    match curr.parse::<f64>() {
+        Ok(num) => Ok((TokenType::NumLiteral(num), i)),
+        Err(_) => Err(ScanError::InvalidNumber { literal: curr, span: Span::new(start_idx, i) })
+    }
 }
 
-//function to scan a word from a string and produce a token containing the appropriate constant, function name, or variable
-fn scan_letters(chars: &Vec<char>, start_idx: usize) -> Result<(TokenType, usize), String> {
+/*function to scan a word from a string and produce a token containing the appropriate constant, function name, or variable - scans the 
+longest recognized word the characters begin with, so that sinh is scanned as sinh rather than sin followed by h, while sinx is still 
+sin followed by x. Words may contain digits after the first letter (atan2), but since only the longest match is kept, x2 is still x 
+followed by 2*/
+fn scan_letters(chars: &[char], start_idx: usize) -> Result<(TokenType, usize), ScanError> {
     let mut curr = String::new();
     let mut i = start_idx;
+    let mut longest = None;
 
-    //create vector of all possible words that the scanner can recognize
-    let mut candidates = vec![RECOGNIZED_CONSTANTS.to_vec(), RECOGNIZED_FUNCTIONS.to_vec(), RECOGNIZED_VARIABLES.to_vec()];
-    let mut candidates_left = true;
-
-    //iterate until we've reached the end of the char array, no words match our current scanned word, or we encounter a non-letter character
-    while i < chars.len() && candidates_left && chars[i].is_alphabetic() {
+    //iterate until we've reached the end of the char array or we encounter a character that can't be part of a word, remembering the longest match so far
+    while i < chars.len() && chars[i].is_alphanumeric() && superscript_value(chars[i]).is_none() {
         curr.push(chars[i]);
-        i = i + 1;
-        candidates = candidates
-                    .iter()
-                    .map(|v| (*v).iter().map(|s| *s).filter(|el| el.starts_with(&curr)).collect()).collect();
-        //for a match to be found, there must be only one candidate left and it must match string exactly
-        if candidates[0].len() + candidates[1].len() + candidates[2].len() == 1 && match_found(&candidates, &curr) { break; }
-        candidates_left = !candidates.iter().all(|el| el.is_empty());
-    }
-
-    let constant_candidates = &candidates[0];
-    let function_candidates = &candidates[1];
-    let variable_candidates = &candidates[2];
-    
-    if !constant_candidates.is_empty() && constant_candidates[0] == curr { return Ok((TokenType::Constant(curr), i)); }
-    else if !function_candidates.is_empty() && function_candidates[0] == curr { return Ok((TokenType::FunctionName(curr), i)); }
-    else if !variable_candidates.is_empty() && variable_candidates[0] == curr { return Ok((TokenType::Variable(curr), i)); }
+        i += 1;
+        if RECOGNIZED_CONSTANTS.contains(&curr.as_str()) { longest = Some((TokenType::Constant(curr.clone()), i)); }
+        else if RECOGNIZED_FUNCTIONS.contains(&curr.as_str()) { longest = Some((TokenType::FunctionName(curr.clone()), i)); }
+        else if RECOGNIZED_VARIABLES.contains(&curr.as_str()) { longest = Some((TokenType::Variable(curr.clone()), i)); }
+        else if let Some(keyword) = scan_keyword(&curr) { longest = Some((keyword, i)); }
+    }
+
+    if let Some(token) = longest { return Ok(token); }
 
-    return Err(format!("Error: invalid word '{curr}' found"))
+    //f followed by a number (f1, or f₁ as the inputs are labelled) refers to the input with that number
+    let digits: String = chars[start_idx + 1..].iter().map_while(|c| subscript_value(*c).or(Some(*c).filter(char::is_ascii_digit))).collect();
+    if chars[start_idx] == 'f' && !digits.is_empty() {
+        let end = start_idx + 1 + digits.chars().count();
+        return Ok((TokenType::UserFunction(format!("f{}", digits)), end));
+    }
+
+    /*any other ascii letter is a single letter parameter, so ab is a * b - unless it is the last letter of a longer word that is called
+    like a function, as in sech(x), in which case the word is most likely a misspelled or unsupported function*/
+    let word_continues = start_idx + 1 < chars.len() && chars[start_idx + 1].is_alphabetic();
+    let word_started = start_idx > 0 && chars[start_idx - 1].is_alphabetic();
+    let called = chars[start_idx + 1..].iter().find(|c| !c.is_whitespace()) == Some(&'(');
+    if chars[start_idx].is_ascii_alphabetic() && !(word_started && !word_continues && called) {
+        return Ok((TokenType::Parameter(chars[start_idx].to_string()), start_idx + 1));
+    }
+    Err(unknown_word_error(chars, start_idx))
 }
 
+//function to produce the token for a keyword used in conditions, if word is one
+fn scan_keyword(word: &str) -> Option<TokenType> {
+    match word {
+        "and" => Some(TokenType::And),
+        "or" => Some(TokenType::Or),
+        "if" => Some(TokenType::If),
+        _ => None
+    }
+}
 
-//function to check if we have found a match based on current token string
-fn match_found<'a>(candidates: &'a Vec<Vec<&'a str>>, search_string: &'a String) -> bool {
-    for c_vec in candidates.iter() {
-        for s in c_vec.iter().map(|s| *s) {
-            if s == search_string { return true; }
-        }
+/*function to produce the error for a word that couldn't be scanned - the error covers the whole run of letters containing idx (so sech
+is reported rather than just the h left over after sec), and a word followed by a left parenthesis is reported as an unknown function*/
+fn unknown_word_error(chars: &[char], idx: usize) -> ScanError {
+    let start = idx - chars[..idx].iter().rev().take_while(|c| c.is_alphabetic()).count();
+    let end = idx + chars[idx..].iter().take_while(|c| c.is_alphabetic()).count();
+    let word: String = chars[start..end].iter().collect();
+    let span = Span::new(start, end);
+    match chars[end..].iter().find(|c| !c.is_whitespace()) {
+        Some('(') => ScanError::UnknownFunction { name: word, span },
+        _ => ScanError::UnknownIdentifier { identifier: word, span }
     }
-    return false;
 }
 
 
@@ -155,23 +454,123 @@ fn match_found<'a>(candidates: &'a Vec<Vec<&'a str>>, search_string: &'a String)
 mod tests {
     use super::*;
 
+    fn scan_types(input: &str) -> Vec<TokenType> {
+        scan(input).unwrap().into_iter().map(|token| token.token_type).collect()
+    }
+
     #[test]
     fn test_scan_num() {
-        let result = scan_number(&"123.45abc".chars().collect(), 0).unwrap().0;
+        let result = scan_number(&"123.45abc".chars().collect::<Vec<char>>(), 0).unwrap().0;
         let expected = TokenType::NumLiteral(123.45);
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_scan_num_exponent() {
+        assert_eq!(scan_number(&"1.5e-3".chars().collect::<Vec<char>>(), 0).unwrap(), (TokenType::NumLiteral(1.5e-3), 6));
+        assert_eq!(scan_number(&"6.02E23".chars().collect::<Vec<char>>(), 0).unwrap(), (TokenType::NumLiteral(6.02e23), 7));
+        assert_eq!(scan_number(&"2e+4".chars().collect::<Vec<char>>(), 0).unwrap(), (TokenType::NumLiteral(2e4), 4));
+        assert!(scan_number(&"1.2.3".chars().collect::<Vec<char>>(), 0).is_err());
+    }
+
+    #[test]
+    fn test_scan_exponent_and_constant_e() {
+        let two = TokenType::NumLiteral(2.0);
+        let e = TokenType::Constant(String::from("e"));
+        let x = TokenType::Variable(String::from("x"));
+        //no digits after the e, so it is the constant
+        assert_eq!(scan_types("2e"), vec![two.clone(), e.clone()]);
+        assert_eq!(scan_types("2e+x"), vec![two.clone(), e.clone(), TokenType::Add, x.clone()]);
+        assert_eq!(scan_types("2ex"), vec![two.clone(), e.clone(), x.clone()]);
+        //an e that starts a word is always the constant
+        assert_eq!(scan_types("e2"), vec![e.clone(), two.clone()]);
+        //whitespace separates the number from the constant
+        assert_eq!(scan_types("2 e-3"), vec![two.clone(), e, TokenType::Sub, TokenType::NumLiteral(3.0)]);
+        //an attached, signed integer makes an exponent
+        assert_eq!(scan_types("2e-3"), vec![TokenType::NumLiteral(2e-3)]);
+        assert_eq!(scan_types("2e3x"), vec![TokenType::NumLiteral(2e3), x]);
+    }
+
     #[test]
     fn test_scan_letters() {
-        let result = scan_letters(&"sin2x".chars().collect(), 0).unwrap().0;
+        let result = scan_letters(&"sin2x".chars().collect::<Vec<char>>(), 0).unwrap().0;
         let expected = TokenType::FunctionName(String::from("sin"));
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_scan_letters_longest_match() {
+        let function = |name: &str| TokenType::FunctionName(String::from(name));
+        let x = TokenType::Variable(String::from("x"));
+        let e = TokenType::Constant(String::from("e"));
+        assert_eq!(scan_types("sinh(x)"), vec![function("sinh"), TokenType::LeftParen, x.clone(), TokenType::RightParen]);
+        assert_eq!(scan_types("sinx"), vec![function("sin"), x.clone()]);
+        assert_eq!(scan_types("acosh"), vec![function("acosh")]);
+        assert_eq!(scan_types("exp"), vec![function("exp")]);
+        assert_eq!(scan_types("ex"), vec![e, x.clone()]);
+        assert_eq!(scan_types("sign"), vec![function("sign")]);
+        assert_eq!(scan_types("xcbrt"), vec![x.clone(), function("cbrt")]);
+        assert_eq!(scan_types("atan2"), vec![function("atan2")]);
+        assert_eq!(scan_types("atan(2"), vec![function("atan"), TokenType::LeftParen, TokenType::NumLiteral(2.0)]);
+        assert_eq!(scan_types("x2"), vec![x.clone(), TokenType::NumLiteral(2.0)]);
+        assert_eq!(scan_types("x²"), vec![x, TokenType::Exp, TokenType::NumLiteral(2.0)]);
+    }
+
+    #[test]
+    fn test_scan_parameters() {
+        let parameter = |name: &str| TokenType::Parameter(String::from(name));
+        let x = TokenType::Variable(String::from("x"));
+        assert_eq!(scan_types("ab"), vec![parameter("a"), parameter("b")]);
+        assert_eq!(scan_types("kx"), vec![parameter("k"), x.clone()]);
+        assert_eq!(scan_types("a(x)"), vec![parameter("a"), TokenType::LeftParen, x.clone(), TokenType::RightParen]);
+        assert_eq!(scan_types("kx(x)")[..2], [parameter("k"), x.clone()]);
+        assert_eq!(scan_types("a sin(x)")[..2], [parameter("a"), TokenType::FunctionName(String::from("sin"))]);
+        assert_eq!(scan_types("xa2"), vec![x, parameter("a"), TokenType::NumLiteral(2.0)]);
+    }
+
+    #[test]
+    fn test_scan_user_functions() {
+        let user_function = |name: &str| TokenType::UserFunction(String::from(name));
+        let x = TokenType::Variable(String::from("x"));
+        assert_eq!(scan_types("f1(x)"), vec![user_function("f1"), TokenType::LeftParen, x.clone(), TokenType::RightParen]);
+        assert_eq!(scan_types("f₂"), vec![user_function("f2")]);
+        assert_eq!(scan_types("g(x) =")[4], TokenType::Equals);
+        let mut tokens = scan("g(x) + g").unwrap();
+        mark_user_functions(&mut tokens, &[String::from("g")]);
+        assert_eq!(tokens[0].token_type, user_function("g"));
+        //a name that isn't called is still a parameter
+        assert_eq!(tokens[5].token_type, TokenType::Parameter(String::from("g")));
+    }
+
+    #[test]
+    fn test_scan_comparisons() {
+        let comparisons = |input: &str| -> Vec<TokenType> {
+            scan_types(input).into_iter().filter(|token| !matches!(token, TokenType::Variable(_) | TokenType::NumLiteral(_))).collect()
+        };
+        assert_eq!(comparisons("y < x"), vec![TokenType::Less]);
+        assert_eq!(comparisons("y <= x"), vec![TokenType::LessEqual]);
+        assert_eq!(comparisons("y ≤ x"), vec![TokenType::LessEqual]);
+        assert_eq!(comparisons("y>=2"), vec![TokenType::GreaterEqual]);
+        assert_eq!(comparisons("y > x"), vec![TokenType::Greater]);
+        assert_eq!(scan("y <= x").unwrap()[1].span, Span::new(2, 4));
+    }
+
+    #[test]
+    fn test_scan_conditions() {
+        let x = TokenType::Variable(String::from("x"));
+        let zero = TokenType::NumLiteral(0.0);
+        assert_eq!(scan_types("{x<0: x}"), vec![TokenType::LeftBrace, x.clone(), TokenType::Less, zero.clone(), TokenType::Colon, x.clone(), 
+                                              TokenType::RightBrace]);
+        assert_eq!(scan_types("if(x>0"), vec![TokenType::If, TokenType::LeftParen, x.clone(), TokenType::Greater, zero.clone()]);
+        assert_eq!(scan_types("x and x or x")[1..4], [TokenType::And, x.clone(), TokenType::Or]);
+        assert_eq!(scan_types("x ∧ x ∨ x")[1..4], [TokenType::And, x, TokenType::Or]);
+        //words beginning with a keyword are still scanned by longest match
+        assert_eq!(scan_types("floor")[0], TokenType::FunctionName(String::from("floor")));
+    }
+
     #[test]
     fn test_scan() {
-        let result = scan(&String::from("x * sin(x) / e")).unwrap();
+        let result = scan_types("x * sin(x) / e");
         let x = TokenType::Variable(String::from("x"));
         let x2 = TokenType::Variable(String::from("x"));
         let mul = TokenType::Mul;
@@ -183,4 +582,79 @@ mod tests {
         let expected = vec![x, mul, sin, lp, x2, rp, div, e];
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_scan_unicode_symbols() {
+        let x = TokenType::Variable(String::from("x"));
+        let pi = TokenType::Constant(String::from("pi"));
+        assert_eq!(scan_types("2×x"), vec![TokenType::NumLiteral(2.0), TokenType::Mul, x.clone()]);
+        assert_eq!(scan_types("2·π"), vec![TokenType::NumLiteral(2.0), TokenType::Mul, pi.clone()]);
+        assert_eq!(scan_types("x÷2"), vec![x.clone(), TokenType::Div, TokenType::NumLiteral(2.0)]);
+        assert_eq!(scan_types("π−x"), vec![pi, TokenType::Sub, x.clone()]);
+        assert_eq!(scan_types("√x"), vec![TokenType::Radical, x]);
+        let theta = TokenType::Variable(String::from("theta"));
+        assert_eq!(scan_types("sin(5θ)")[3], theta);
+        assert_eq!(scan_types("theta/4"), vec![theta, TokenType::Div, TokenType::NumLiteral(4.0)]);
+    }
+
+    #[test]
+    fn test_scan_pipes() {
+        let pipes = |input: &str| -> String {
+            scan_types(input).into_iter().filter_map(|token| match token {
+                TokenType::LeftPipe => Some('<'),
+                TokenType::RightPipe => Some('>'),
+                _ => None
+            }).collect()
+        };
+        assert_eq!(pipes("|x|"), "<>");
+        assert_eq!(pipes("||x|-1|"), "<<>>");
+        assert_eq!(pipes("|x||pi|"), "<><>");
+        assert_eq!(pipes("|x|-|2|"), "<><>");
+        assert_eq!(pipes("||x|*|e||"), "<<><>>");
+        assert_eq!(pipes("2|x - 1|(x)"), "<>");
+        assert_eq!(pipes("|sin(x)|"), "<>");
+        assert_eq!(pipes("x|"), "<");
+    }
+
+    #[test]
+    fn test_scan_mod() {
+        let x = TokenType::Variable(String::from("x"));
+        let three = TokenType::NumLiteral(3.0);
+        let function = TokenType::FunctionName(String::from("mod"));
+        assert_eq!(scan_types("x mod 3"), vec![x.clone(), TokenType::Mod, three.clone()]);
+        assert_eq!(scan_types("x % 3"), vec![x.clone(), TokenType::Mod, three.clone()]);
+        assert_eq!(scan_types("x! mod (x+1)")[2], TokenType::Mod);
+        assert_eq!(scan_types("mod(x, 3)")[0], function);
+        assert_eq!(scan_types("2mod(x, 3)")[1], function);
+        assert_eq!(scan_types("(x)mod(max(x, 1), 3)")[3], function);
+    }
+
+    #[test]
+    fn test_scan_superscripts() {
+        let x = TokenType::Variable(String::from("x"));
+        assert_eq!(scan_types("x²"), vec![x.clone(), TokenType::Exp, TokenType::NumLiteral(2.0)]);
+        assert_eq!(scan_types("x¹⁰"), vec![x.clone(), TokenType::Exp, TokenType::NumLiteral(10.0)]);
+        assert_eq!(scan_types("x⁻³"), vec![x.clone(), TokenType::Exp, TokenType::Sub, TokenType::NumLiteral(3.0)]);
+        assert_eq!(scan_types("2²"), vec![TokenType::NumLiteral(2.0), TokenType::Exp, TokenType::NumLiteral(2.0)]);
+        assert_eq!(scan_types("3x²"), vec![TokenType::NumLiteral(3.0), x.clone(), TokenType::Exp, TokenType::NumLiteral(2.0)]);
+        assert_eq!(scan("x²").unwrap()[2].span, Span::new(1, 2));
+        assert_eq!(scan("x⁻").unwrap_err(), ScanError::UnexpectedCharacter { character: '⁻', span: Span::new(1, 2) });
+    }
+
+    #[test]
+    fn test_scan_spans() {
+        let spans: Vec<Span> = scan("12.5 * sin(x)").unwrap().into_iter().map(|token| token.span).collect();
+        let expected = vec![Span::new(0, 4), Span::new(5, 6), Span::new(7, 10), Span::new(10, 11), Span::new(11, 12), Span::new(12, 13)];
+        assert_eq!(spans, expected);
+    }
+
+    #[test]
+    fn test_scan_error_spans() {
+        assert_eq!(scan("x + $").unwrap_err(), ScanError::UnexpectedCharacter { character: '$', span: Span::new(4, 5) });
+        assert_eq!(scan("2 + αβ").unwrap_err(), ScanError::UnknownIdentifier { identifier: String::from("αβ"), span: Span::new(4, 6) });
+        assert_eq!(scan("2 + sech(x)").unwrap_err(), ScanError::UnknownFunction { name: String::from("sech"), span: Span::new(4, 8) });
+        assert_eq!(scan("xα").unwrap_err(), ScanError::UnknownIdentifier { identifier: String::from("xα"), span: Span::new(0, 2) });
+        assert_eq!(scan("ab(x)").unwrap_err(), ScanError::UnknownFunction { name: String::from("ab"), span: Span::new(0, 2) });
+        assert_eq!(scan("1.2.3 + x").unwrap_err(), ScanError::InvalidNumber { literal: String::from("1.2.3"), span: Span::new(0, 5) });
+    }
 }
\ No newline at end of file
@@ -0,0 +1,359 @@
+/*
+SIMPLIFICATION:
+Expressions are rewritten from their leaves up, in one of two modes. simplify makes an expression read the way it would be written by hand,
+as for the derivatives shown next to each function (see derivative.rs), and optimize makes it quicker to calculate, just before its
+instructions are generated (see generate_calculator in calculator.rs). Both:
+    fold numbers        2 * 3 + x is x + 6, and sqrt(4) is 2
+    remove identities   x * 1, x + 0, x / 1 and x ^ 1 are x, and -(-x) is x
+    collect terms       x + 2x - y is 3x - y, and x * 2 * y * 3 is 6 * (x * y)
+    order operands      the terms of a sum and the factors of a product are sorted, numbers first in a product and last in a sum, so that
+                        expressions that only differ in the order of their operands are written the same way
+    decide conditions   {1 < 2: x, y} is x
+
+A readable expression keeps constants such as pi, only folds numbers where the result can be written with at most 6 decimal places, and
+removes anything multiplied by 0. An optimized expression folds constants and every calculation on numbers alone, but stays undefined
+exactly where it was before - x * 0 is kept, since x may be undefined, and 1 / 0 isn't folded. Terms are reordered and regrouped, so an
+optimized expression may round differently in the last few bits.
+*/
+
+use std::f64::consts::{E, PI};
+
+use crate::{calculator::{apply_function, compare, factorial, floored_mod}, parser::{ASTNode, ASTNodeType}, scanner::{Span, TokenType}};
+
+//how an expression is simplified
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Mode {
+    Readable,
+    Optimized
+}
+
+impl Mode {
+    //function to check whether a number calculated while simplifying can replace the expression it was calculated from
+    fn accepts(self, n: f64) -> bool {
+        match self {
+            Mode::Readable => (n * 1e6).fract() == 0.0,
+            Mode::Optimized => true
+        }
+    }
+}
+
+//function to simplify an expression so that it reads more naturally
+pub fn simplify(expression: ASTNode) -> ASTNode {
+    rewrite(&expression, Mode::Readable)
+}
+
+//function to simplify an expression so that it takes fewer instructions to calculate, without changing where it is defined
+pub fn optimize(expression: ASTNode) -> ASTNode {
+    rewrite(&expression, Mode::Optimized)
+}
+
+fn rewrite(expression: &ASTNode, mode: Mode) -> ASTNode {
+    let span = expression.span;
+    let node_type = match expression.map_children(|child| rewrite(child, mode)) {
+        ASTNodeType::BinaryExpression(operator, u, v) => {
+            let node = binary(operator, *u, *v, span);
+            return match &node.node_type {
+                ASTNodeType::BinaryExpression(TokenType::Add | TokenType::Sub, _, _) => sum(&node, mode),
+                ASTNodeType::BinaryExpression(TokenType::Mul, _, _) => product(&node, mode),
+                _ => quotient_or_power(node, mode)
+            };
+        },
+        ASTNodeType::UnaryExpression(TokenType::Sub, u) => return negation(*u, span),
+        ASTNodeType::UnaryExpression(TokenType::Factorial, u) => {
+            match value(&u, mode).and_then(factorial) {
+                Some(n) if mode.accepts(n) => return number(n, span),
+                _ => ASTNodeType::UnaryExpression(TokenType::Factorial, u)
+            }
+        },
+        ASTNodeType::FunctionExpression(TokenType::FunctionName(name), arguments) => {
+            let values: Option<Vec<f64>> = arguments.iter().map(|argument| value(argument, mode)).collect();
+            match values.and_then(|values| apply_function(&name, &values)) {
+                Some(n) if mode.accepts(n) => return number(n, span),
+                _ => ASTNodeType::FunctionExpression(TokenType::FunctionName(name), arguments)
+            }
+        },
+        ASTNodeType::AtomicExpression(TokenType::Constant(c)) if mode == Mode::Optimized => {
+            return number(if c == "pi" { PI } else { E }, span);
+        },
+        ASTNodeType::InequalityExpression(operator, u, v) => {
+            if let (Some(a), Some(b)) = (literal(&u), literal(&v)) { return number(compare(&operator, a, b), span); }
+            ASTNodeType::InequalityExpression(operator, u, v)
+        },
+        //a condition that is decided by its left side doesn't need its right side
+        ASTNodeType::LogicalExpression(operator, u, v) => {
+            match (&operator, literal(&u)) {
+                (TokenType::And, Some(holds)) => return if holds == 0.0 { *u } else { *v },
+                (_, Some(holds)) => return if holds == 0.0 { *v } else { number(1.0, span) },
+                _ => ASTNodeType::LogicalExpression(operator, u, v)
+            }
+        },
+        //branches whose conditions never hold are removed, and a branch whose condition always holds ends the expression
+        ASTNodeType::PiecewiseExpression(branches, mut default) => {
+            let mut remaining = Vec::new();
+            for (condition, value) in branches {
+                match literal(&condition) {
+                    Some(0.0) => continue,
+                    Some(_) => {
+                        default = Some(Box::new(value));
+                        break;
+                    },
+                    None => remaining.push((condition, value))
+                }
+            }
+            match (remaining.is_empty(), default) {
+                (true, Some(default)) => return *default,
+                (_, default) => ASTNodeType::PiecewiseExpression(remaining, default)
+            }
+        },
+        node_type => node_type
+    };
+    ASTNode::new(node_type, span)
+}
+
+/*function to simplify a sum of terms, each of which has already been simplified - like terms are collected by adding their coefficients,
+and the terms are written in order, as additions and subtractions*/
+fn sum(expression: &ASTNode, mode: Mode) -> ASTNode {
+    let mut terms = Vec::new();
+    collect_terms(expression, false, &mut terms);
+    let mut groups: Vec<(f64, Option<ASTNode>)> = Vec::new();
+    for term in terms {
+        let (coefficient, rest) = split_coefficient(term);
+        let key = rest.as_ref().map(|rest| rest.to_string());
+        let group = groups.iter_mut().find(|(c, other)| other.as_ref().map(|other| other.to_string()) == key && mode.accepts(c + coefficient));
+        match group {
+            Some(group) => group.0 += coefficient,
+            None => groups.push((coefficient, rest))
+        }
+    }
+    //a term multiplied by 0 can only be removed from a readable expression, as it may be undefined
+    groups.retain(|(coefficient, rest)| *coefficient != 0.0 || (rest.is_some() && mode == Mode::Optimized));
+    groups.sort_by_cached_key(|(_, rest)| rest.as_ref().map_or((u8::MAX, String::new()), order));
+    let span = expression.span;
+    let mut groups = groups.into_iter();
+    let Some((coefficient, rest)) = groups.next() else { return number(0.0, span); };
+    let mut result = term(coefficient, rest, span);
+    for (coefficient, rest) in groups {
+        result = if coefficient < 0.0 { binary(TokenType::Sub, result, term(-coefficient, rest, span), span) }
+                 else { binary(TokenType::Add, result, term(coefficient, rest, span), span) };
+    }
+    result
+}
+
+//function to add the terms of a sum to terms, flattening nested sums and differences - negated terms are negated when added
+fn collect_terms(expression: &ASTNode, negated: bool, terms: &mut Vec<ASTNode>) {
+    match &expression.node_type {
+        ASTNodeType::BinaryExpression(TokenType::Add, u, v) => {
+            collect_terms(u, negated, terms);
+            collect_terms(v, negated, terms);
+        },
+        ASTNodeType::BinaryExpression(TokenType::Sub, u, v) => {
+            collect_terms(u, negated, terms);
+            collect_terms(v, !negated, terms);
+        },
+        _ if negated => terms.push(negation(expression.clone(), expression.span)),
+        _ => terms.push(expression.clone())
+    }
+}
+
+//function to split a simplified term into its numeric coefficient and the rest of it, if it is more than a number
+fn split_coefficient(term: ASTNode) -> (f64, Option<ASTNode>) {
+    match term.node_type {
+        ASTNodeType::AtomicExpression(TokenType::NumLiteral(n)) => (n, None),
+        ASTNodeType::UnaryExpression(TokenType::Sub, u) => (-1.0, Some(*u)),
+        ASTNodeType::BinaryExpression(TokenType::Mul, u, v) if literal(&u).is_some() => (literal(&u).unwrap(), Some(*v)),
+        node_type => (1.0, Some(ASTNode::new(node_type, term.span)))
+    }
+}
+
+//function to produce a term from its coefficient and the rest of it
+fn term(coefficient: f64, rest: Option<ASTNode>, span: Span) -> ASTNode {
+    match rest {
+        None => number(coefficient, span),
+        Some(rest) if coefficient == 1.0 => rest,
+        Some(rest) if coefficient == -1.0 => negation(rest, span),
+        Some(rest) => binary(TokenType::Mul, number(coefficient, span), rest, span)
+    }
+}
+
+/*function to simplify a product of factors, each of which has already been simplified - numbers and negations are combined into one
+coefficient, written before the other factors in order*/
+fn product(expression: &ASTNode, mode: Mode) -> ASTNode {
+    let mut factors = Vec::new();
+    collect_factors(expression, &mut factors);
+    let mut coefficient = 1.0;
+    let mut others = Vec::new();
+    for factor in factors {
+        match factor.node_type {
+            ASTNodeType::AtomicExpression(TokenType::NumLiteral(n)) if mode.accepts(coefficient * n) => coefficient *= n,
+            ASTNodeType::UnaryExpression(TokenType::Sub, u) => {
+                coefficient = -coefficient;
+                others.push(*u);
+            },
+            _ => others.push(factor)
+        }
+    }
+    let span = expression.span;
+    if coefficient == 0.0 && mode == Mode::Readable { return number(0.0, span); }
+    others.sort_by_cached_key(order);
+    let mut others = others.into_iter();
+    let Some(first) = others.next() else { return number(coefficient, span); };
+    let rest = others.fold(first, |product, factor| binary(TokenType::Mul, product, factor, span));
+    term(coefficient, Some(rest), span)
+}
+
+//function to add the factors of a product to factors, flattening nested products
+fn collect_factors(expression: &ASTNode, factors: &mut Vec<ASTNode>) {
+    match &expression.node_type {
+        ASTNodeType::BinaryExpression(TokenType::Mul, u, v) => {
+            collect_factors(u, factors);
+            collect_factors(v, factors);
+        },
+        _ => factors.push(expression.clone())
+    }
+}
+
+//function to simplify a quotient, power or modulo whose operands have already been simplified
+fn quotient_or_power(expression: ASTNode, mode: Mode) -> ASTNode {
+    let span = expression.span;
+    let ASTNodeType::BinaryExpression(operator, u, v) = expression.node_type else { return expression; };
+    let (u, v) = (*u, *v);
+    if let (Some(a), Some(b)) = (literal(&u), literal(&v)) {
+        let folded = match operator {
+            TokenType::Div if b != 0.0 => Some(a / b),
+            TokenType::Exp => Some(a.powf(b)).filter(|n| !n.is_nan()),
+            TokenType::Mod => floored_mod(a, b),
+            _ => None
+        };
+        if let Some(n) = folded.filter(|n| mode.accepts(*n)) { return number(n, span); }
+    }
+    match (&operator, literal(&u), literal(&v)) {
+        (TokenType::Div | TokenType::Exp, _, Some(1.0)) => u,
+        (TokenType::Div, Some(0.0), _) if mode == Mode::Readable => number(0.0, span),
+        (TokenType::Exp, _, Some(0.0)) if mode == Mode::Readable => number(1.0, span),
+        //negations are moved out of quotients, so that they can be collected with the terms of a sum
+        (TokenType::Div, _, _) => {
+            let ((u, u_negated), (v, v_negated)) = (strip_negation(u), strip_negation(v));
+            let quotient = binary(operator, u, v, span);
+            if u_negated != v_negated { negation(quotient, span) } else { quotient }
+        },
+        _ => binary(operator, u, v, span)
+    }
+}
+
+//function to negate a simplified expression, by negating a number or coefficient, or removing a negation, where it can
+fn negation(expression: ASTNode, span: Span) -> ASTNode {
+    match expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::NumLiteral(n)) => number(-n, span),
+        ASTNodeType::UnaryExpression(TokenType::Sub, u) => *u,
+        ASTNodeType::BinaryExpression(TokenType::Mul, u, v) if literal(&u).is_some() => term(-literal(&u).unwrap(), Some(*v), span),
+        node_type => negate(ASTNode::new(node_type, expression.span), span)
+    }
+}
+
+/*function to split a simplified expression into what it negates, if it is negated, a negative number or has a negative coefficient, and
+whether it was negated*/
+fn strip_negation(expression: ASTNode) -> (ASTNode, bool) {
+    match expression.node_type {
+        ASTNodeType::UnaryExpression(TokenType::Sub, u) => (*u, true),
+        ASTNodeType::AtomicExpression(TokenType::NumLiteral(n)) if n < 0.0 => (number(-n, expression.span), true),
+        ASTNodeType::BinaryExpression(TokenType::Mul, u, v) if literal(&u).is_some_and(|n| n < 0.0) => {
+            (term(-literal(&u).unwrap(), Some(*v), expression.span), true)
+        },
+        node_type => (ASTNode::new(node_type, expression.span), false)
+    }
+}
+
+/*function to get the key operands are sorted by - numbers, then constants, parameters, variables, powers, functions and anything else,
+with those of the same kind in the order they are written*/
+fn order(expression: &ASTNode) -> (u8, String) {
+    let rank = match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::NumLiteral(_)) => 0,
+        ASTNodeType::AtomicExpression(TokenType::Constant(_)) => 1,
+        ASTNodeType::AtomicExpression(TokenType::Parameter(_)) => 2,
+        ASTNodeType::AtomicExpression(_) => 3,
+        ASTNodeType::BinaryExpression(TokenType::Exp, _, _) => 4,
+        ASTNodeType::FunctionExpression(_, _) => 5,
+        _ => 6
+    };
+    (rank, expression.to_string())
+}
+
+//function to get the value of a number, or of a constant in a readable expression (an optimized expression has no constants left)
+fn value(expression: &ASTNode, mode: Mode) -> Option<f64> {
+    match &expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::Constant(c)) if mode == Mode::Readable => Some(if c == "pi" { PI } else { E }),
+        _ => literal(expression)
+    }
+}
+
+//function to get the value of a number literal
+pub fn literal(expression: &ASTNode) -> Option<f64> {
+    match expression.node_type {
+        ASTNodeType::AtomicExpression(TokenType::NumLiteral(n)) => Some(n),
+        _ => None
+    }
+}
+
+pub fn number(n: f64, span: Span) -> ASTNode {
+    ASTNode::new(ASTNodeType::AtomicExpression(TokenType::NumLiteral(n)), span)
+}
+
+pub fn binary(operator: TokenType, left: ASTNode, right: ASTNode, span: Span) -> ASTNode {
+    ASTNode::new(ASTNodeType::BinaryExpression(operator, Box::new(left), Box::new(right)), span)
+}
+
+pub fn negate(expression: ASTNode, span: Span) -> ASTNode {
+    ASTNode::new(ASTNodeType::UnaryExpression(TokenType::Sub, Box::new(expression)), span)
+}
+
+pub fn call(name: &str, arguments: Vec<ASTNode>, span: Span) -> ASTNode {
+    ASTNode::new(ASTNodeType::FunctionExpression(TokenType::FunctionName(String::from(name)), arguments), span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parser::parse, scanner::scan};
+
+    fn simplified(expression: &str) -> String {
+        simplify(parse(&scan(expression).unwrap()).unwrap()).to_string()
+    }
+
+    fn optimized(expression: &str) -> String {
+        optimize(parse(&scan(expression).unwrap()).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(simplified("x * 1 + 0"), "x");
+        assert_eq!(simplified("--x"), "x");
+        assert_eq!(simplified("2 * 3 + x"), "(x + 6)");
+        assert_eq!(simplified("x + 2x - y"), "((3 * x) - y)");
+        assert_eq!(simplified("x * 2 * y * 3"), "(6 * (x * y))");
+        assert_eq!(simplified("y x + x y"), "(2 * (x * y))");
+        assert_eq!(simplified("x - x + 1"), "1");
+        assert_eq!(simplified("0 * ln(x) + x^1"), "x");
+        assert_eq!(simplified("-x / -2"), "(x / 2)");
+        assert_eq!(simplified("sqrt(4) + cos(pi) + x^0"), "2");
+        //numbers that can't be written exactly, and constants, are left as they are
+        assert_eq!(simplified("2 pi / 3"), "((2 * pi) / 3)");
+        assert_eq!(simplified("0.5 * 3 x"), "(1.5 * x)");
+        assert_eq!(simplified("{1 < 2: x, y}"), "x");
+        assert_eq!(simplified("{1 > 0 or x < 0: x, y}"), "x");
+        assert_eq!(simplified("{2 < 1: x, x < 0: y}"), "{x < 0: y}");
+    }
+
+    #[test]
+    fn test_optimize() {
+        assert_eq!(optimized("2 * pi / 4"), (PI / 2.0).to_string());
+        assert_eq!(optimized("-x"), "(-x)");
+        assert_eq!(optimized("x * 1 + 0 - -a"), "(a + x)");
+        assert_eq!(optimized("sin(x) * 2 + 2 sin(x)"), "(4 * sin(x))");
+        //an optimized expression is undefined wherever the original was
+        assert_eq!(optimized("0 * ln(x)"), "(0 * ln(x))");
+        assert_eq!(optimized("ln(x) - ln(x)"), "(0 * ln(x))");
+        assert_eq!(optimized("1 / (1 - 1)"), "(1 / 0)");
+        assert_eq!(optimized("(-1)!"), "(-1!)");
+        assert_eq!(optimized("{1 > 2: x}"), "{}");
+    }
+}